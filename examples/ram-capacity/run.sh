@@ -15,41 +15,40 @@ GRAPH_OUTPUT_HEIGHT=$((1000))
 GRAPH_OUTPUT_FORMAT="png"
 
 # Ram capacities to test
-RAM_CAPACITIES=$(seq -s " " 200 250 4500)
+RAM_CAPACITIES="200..=4500 step 250"
 
 
 # Build everything before-hand
 cargo build --profile "$PROFILE" -p ftmemsim -p ftmemsim-graphs
-mkdir -p config output graphs
-
-# Simulate all configs and then generate the graphs
+mkdir -p output graphs
+
+# Simulate all ram capacities in a single pass over the trace
+printf "Simulating all ram capacities\n"
+cargo run -q --profile "$PROFILE" -p ftmemsim -- \
+	sweep \
+	"$TRACE_FILE" \
+	--base-config "base-config.json" \
+	--axis "hemem.memories[0].page_capacity=$RAM_CAPACITIES" \
+	--output-dir "output" \
+	--summary-json "output/summary.json"
+
+# Show the performance, energy and cost of each ram capacity
+printf "Ram capacity\tSimulated time (ns)\tEnergy (J)\tCost ($)\n"
+jq -r -n \
+	--slurpfile manifest "output/manifest.json" \
+	--slurpfile summaries "output/summary.json" \
+	'[$manifest[0].runs, $summaries[0]] | transpose[] | [.[0].params["hemem.memories[0].page_capacity"], .[1].simulated_time_ns, .[1].energy_j, .[1].cost_usd] | @tsv'
+
+# Finally run the graphs
 pids=""
 outputs_files=""
-for ram_capacity in $RAM_CAPACITIES; do
-	config_file="config/$ram_capacity.json"
-	output_file="output/$ram_capacity.bin.gz"
+while read -r config_file output_file ram_capacity; do
+	config_file="output/$config_file"
+	output_file="output/$output_file"
 	graph_migrations_file="graphs/migrations-$ram_capacity.$GRAPH_OUTPUT_FORMAT"
 
 	outputs_files+="$output_file "
 
-	printf "Simulating $config_file\n"
-
-	# Create the config file and change it's page capacity
-	cp "base-config.json" "$config_file" \
-		&& \
-
-	jq ".hemem.memories[0].page_capacity = $ram_capacity" "$config_file" \
-		| sponge "$config_file" \
-		&& \
-
-	# Then run the simulation
-	cargo run -q --profile "$PROFILE" -p ftmemsim -- \
-		--config "$config_file" \
-		"$TRACE_FILE" \
-		--output "$output_file" \
-		&& \
-
-	# Finally run the graphs
 	cargo run -q --profile "$PROFILE" -p ftmemsim-graphs -- \
 		page-migrations \
 		"$output_file" \
@@ -59,12 +58,11 @@ for ram_capacity in $RAM_CAPACITIES; do
 		--output-height "$GRAPH_OUTPUT_HEIGHT" \
 		&
 
-
 	pids+="$! "
-done
+done < <(jq -r '.runs[] | [.config_file, .output_file, .params["hemem.memories[0].page_capacity"]] | @tsv' "output/manifest.json")
 
 wait $pids
-printf "Finished simulating\n"
+printf "Finished creating graphs\n"
 
 printf "Generating \`page-migrations-hist-multiple\` graph for $outputs_files"
 graph_migrations_hist_file="graphs/migrations-hist.$GRAPH_OUTPUT_FORMAT"
@@ -48,6 +48,12 @@ pub enum SubCmd {
 
 	#[clap(name = "memory-occupancy")]
 	MemoryOccupancy(MemoryOccupancy),
+
+	#[clap(name = "fast-hit-rate")]
+	FastHitRate(FastHitRate),
+
+	#[clap(name = "memory-bandwidth")]
+	MemoryBandwidth(MemoryBandwidth),
 }
 
 /// Creates a graph for page migrations
@@ -169,6 +175,40 @@ pub struct MemoryOccupancy {
 	pub line_width: f64,
 }
 
+/// Fast memory hit rate, per epoch
+#[derive(Debug, clap::Args)]
+pub struct FastHitRate {
+	/// Input
+	pub input_file: PathBuf,
+
+	/// Output
+	#[clap(flatten)]
+	pub output: Output,
+
+	/// Line width
+	#[clap(long = "line-width", default_value_t = 1.0)]
+	pub line_width: f64,
+}
+
+/// Memory bandwidth, per epoch
+#[derive(Debug, clap::Args)]
+pub struct MemoryBandwidth {
+	/// Input
+	pub input_file: PathBuf,
+
+	/// Config file
+	#[clap(long = "config")]
+	pub config_file: PathBuf,
+
+	/// Output
+	#[clap(flatten)]
+	pub output: Output,
+
+	/// Line width
+	#[clap(long = "line-width", default_value_t = 1.0)]
+	pub line_width: f64,
+}
+
 /// Output
 #[derive(Debug, clap::Args)]
 pub struct Output {
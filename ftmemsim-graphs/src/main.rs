@@ -36,6 +36,8 @@ fn main() -> Result<(), anyhow::Error> {
 		args::SubCmd::PageTemperature(cmd_args) => self::draw_page_temperature(cmd_args)?,
 		args::SubCmd::PageTemperatureAvg(cmd_args) => self::draw_page_temperature_avg(cmd_args)?,
 		args::SubCmd::MemoryOccupancy(cmd_args) => self::draw_memory_occupancy(cmd_args)?,
+		args::SubCmd::FastHitRate(cmd_args) => self::draw_fast_hit_rate(cmd_args)?,
+		args::SubCmd::MemoryBandwidth(cmd_args) => self::draw_memory_bandwidth(cmd_args)?,
 	}
 
 	Ok(())
@@ -96,7 +98,7 @@ fn draw_page_migrations(cmd_args: &args::PageMigrations) -> Result<(), anyhow::E
 		// Note: We use the red to dictate the current memory and green for the previous,
 		//       this is to a greener color indicates a positive migration, while a redder
 		//       color a negative migration
-		let max_mem_idx = config.hemem.memories.len();
+		let max_mem_idx = self::memory_names(&config).len();
 		let color = LinSrgb::new(
 			cur_mem_idx as f64 / max_mem_idx as f64,
 			prev_mem_idx as f64 / max_mem_idx as f64,
@@ -105,14 +107,11 @@ fn draw_page_migrations(cmd_args: &args::PageMigrations) -> Result<(), anyhow::E
 		let color = format!("#{:x}", color.into_format::<u8>());
 
 		// Then get the memories (for then ames)
-		let prev_mem = config
-			.hemem
-			.memories
+		let mem_names = self::memory_names(&config);
+		let prev_mem_name = mem_names
 			.get(prev_mem_idx)
 			.expect("Config had less memories than input file");
-		let cur_mem = config
-			.hemem
-			.memories
+		let cur_mem_name = mem_names
 			.get(cur_mem_idx)
 			.expect("Config had less memories than input file");
 
@@ -126,7 +125,7 @@ fn draw_page_migrations(cmd_args: &args::PageMigrations) -> Result<(), anyhow::E
 			points_migrations.iter().map(|p| p.x),
 			points_migrations.iter().map(|p| p.y),
 			&[
-				PlotOption::Caption(&format!("Page migrations ({} to {})", prev_mem.name, cur_mem.name)),
+				PlotOption::Caption(&format!("Page migrations ({prev_mem_name} to {cur_mem_name})")),
 				PlotOption::Color(&color),
 				PlotOption::PointSymbol('O'),
 				PlotOption::PointSize(point_size),
@@ -232,10 +231,7 @@ fn draw_page_location(cmd_args: args::PageLocation) -> Result<(), anyhow::Error>
 		x: f64,
 		y: usize,
 	}
-	let all_points = data
-		.hemem
-		.page_accesses
-		.accesses
+	let all_points = self::page_accesses(&data)?
 		.iter()
 		.map(|page_access| (page_access.mem_idx, page_access))
 		.into_group_map()
@@ -256,10 +252,9 @@ fn draw_page_location(cmd_args: args::PageLocation) -> Result<(), anyhow::Error>
 	let mut fg = gnuplot::Figure::new();
 	let axes_2d = fg.axes2d();
 
+	let mem_names = self::memory_names(&config);
 	for (&mem_idx, points) in all_points.iter().rev() {
-		let mem = config
-			.hemem
-			.memories
+		let mem_name = mem_names
 			.get(mem_idx)
 			.context("Config had less memories than input file")?;
 
@@ -277,7 +272,7 @@ fn draw_page_location(cmd_args: args::PageLocation) -> Result<(), anyhow::Error>
 		let point_size = point_size_progress * cmd_args.point_size;
 
 		axes_2d.points(points.iter().map(|p| p.x), points.iter().map(|p| p.y), &[
-			PlotOption::Caption(&format!("Page location {mem_idx:?} ({})", mem.name)),
+			PlotOption::Caption(&format!("Page location {mem_idx:?} ({mem_name})")),
 			PlotOption::Color(&color),
 			PlotOption::PointSymbol('O'),
 			PlotOption::PointSize(point_size),
@@ -303,10 +298,7 @@ fn draw_page_temperature(cmd_args: args::PageTemperature) -> Result<(), anyhow::
 	// Then index the page pointers.
 	let page_ptr_idxs = self::page_ptr_idxs(&data);
 
-	let max_temp = data
-		.hemem
-		.page_accesses
-		.accesses
+	let max_temp = self::page_accesses(&data)?
 		.iter()
 		.map(|page_access| page_access.cur_temp)
 		.max()
@@ -317,10 +309,7 @@ fn draw_page_temperature(cmd_args: args::PageTemperature) -> Result<(), anyhow::
 		x: f64,
 		y: usize,
 	}
-	let all_points = data
-		.hemem
-		.page_accesses
-		.accesses
+	let all_points = self::page_accesses(&data)?
 		.iter()
 		.map(|page_access| {
 			let point = Point {
@@ -362,7 +351,7 @@ fn draw_page_temperature(cmd_args: args::PageTemperature) -> Result<(), anyhow::
 	fg_axes2d
 		.set_x_label("Time (normalized)", &[])
 		.set_y_label("Page (indexed)", &[])
-		.set_cb_label("Temperature", &[])
+		.set_cb_label(&self::temperature_label(&data), &[])
 		.set_x_range(AutoOption::Fix(0.0), AutoOption::Fix(1.0))
 		.set_y_range(AutoOption::Fix(0.0), AutoOption::Fix(page_ptr_idxs.len() as f64))
 		.set_cb_range(AutoOption::Fix(0.0), AutoOption::Fix(max_temp as f64));
@@ -386,10 +375,7 @@ fn draw_page_temperature_avg(cmd_args: args::PageTemperatureAvg) -> Result<(), a
 		temp_avg:     f64,
 		temp_err:     f64,
 	}
-	let points = data
-		.hemem
-		.page_accesses
-		.accesses
+	let points = self::page_accesses(&data)?
 		.iter()
 		.map(|page_access| (page_access.page_ptr, page_access))
 		.into_group_map()
@@ -429,7 +415,7 @@ fn draw_page_temperature_avg(cmd_args: args::PageTemperatureAvg) -> Result<(), a
 				PlotOption::LineWidth(cmd_args.line_width),
 			],
 		)
-		.set_x_label("Temperature", &[])
+		.set_x_label(&self::temperature_label(&data), &[])
 		.set_y_label("Page (indexed)", &[])
 		.set_x_range(AutoOption::Fix(0.0), AutoOption::Fix(max_avg_temp))
 		.set_y_range(AutoOption::Fix(0.0), AutoOption::Fix(page_ptr_idxs.len() as f64));
@@ -448,15 +434,181 @@ fn draw_memory_occupancy(cmd_args: args::MemoryOccupancy) -> Result<(), anyhow::
 		.with_context(|| format!("Unable to read data file: {:?}", cmd_args.input_file))?;
 
 	// Calculate all the occupancies over time
-	let mut memories_occupancy = (0..config.hemem.memories.len())
+	// Note: If we have epochs, we can use their occupancies directly, else we need to replay every migration
+	let occupancies = match data.hemem.epochs.is_empty() {
+		true => self::memory_occupancies_from_migrations(&config, &data),
+		false => self::memory_occupancies_from_epochs(&data),
+	};
+
+	// Finally create the plot
+	let mut fg = gnuplot::Figure::new();
+	let fg_axes2d = fg.axes2d();
+
+	let mem_names = self::memory_names(&config);
+	for (mem_idx, occupancies) in occupancies {
+		let color_progress = 1.0 - mem_idx as f64 / (mem_names.len() as f64 - 1.0);
+		let color = LinSrgb::new(1.0, 0.0, 0.0).mix(LinSrgb::new(0.0, 1.0, 0.0), color_progress);
+		let color = format!("#{:x}", color.into_format::<u8>());
+
+		let mem_name = mem_names
+			.get(mem_idx)
+			.expect("Config had less memories than input file");
+
+		fg_axes2d.lines(
+			occupancies.iter().map(|(time, _)| time),
+			occupancies.iter().map(|(_, occupancy)| occupancy),
+			&[
+				PlotOption::Caption(&format!("Memory {mem_idx:?} ({mem_name})")),
+				PlotOption::Color(&color),
+				PlotOption::LineWidth(cmd_args.line_width),
+			],
+		);
+	}
+
+	fg_axes2d
+		.set_x_label("Time (normalized)", &[])
+		.set_y_label("Page occupancy", &[])
+		.set_x_range(AutoOption::Fix(0.0), AutoOption::Fix(1.0))
+		.set_y_range(AutoOption::Fix(0.0), AutoOption::Auto);
+
+	// Then output the plot
+	self::handle_output(&cmd_args.output, &mut fg).context("Unable to handle output")?;
+
+	Ok(())
+}
+
+fn draw_fast_hit_rate(cmd_args: args::FastHitRate) -> Result<(), anyhow::Error> {
+	// Parse the input file
+	let data = self::read_data(&cmd_args.input_file)
+		.with_context(|| format!("Unable to read data file: {:?}", cmd_args.input_file))?;
+	anyhow::ensure!(
+		!data.hemem.epochs.is_empty(),
+		"Data had no epochs, set `hemem.epoch_length` in the config"
+	);
+
+	// Finally create the plot
+	let mut fg = gnuplot::Figure::new();
+	fg.axes2d()
+		.lines(
+			data.hemem
+				.epochs
+				.iter()
+				.map(|epoch| self::date_normalized(epoch.end_time - 1, &data)),
+			data.hemem.epochs.iter().map(|epoch| epoch.fast_hit_rate),
+			&[
+				PlotOption::Caption("Fast memory hit rate"),
+				PlotOption::Color("black"),
+				PlotOption::LineWidth(cmd_args.line_width),
+			],
+		)
+		.set_x_label("Time (normalized)", &[])
+		.set_y_label("Hit rate", &[])
+		.set_x_range(AutoOption::Fix(0.0), AutoOption::Fix(1.0))
+		.set_y_range(AutoOption::Fix(0.0), AutoOption::Fix(1.0));
+
+	// Then output the plot
+	self::handle_output(&cmd_args.output, &mut fg).context("Unable to handle output")?;
+
+	Ok(())
+}
+
+fn draw_memory_bandwidth(cmd_args: args::MemoryBandwidth) -> Result<(), anyhow::Error> {
+	// Parse the config and input file
+	let config = self::read_config(&cmd_args.config_file)
+		.with_context(|| format!("Unable to read config file: {:?}", cmd_args.config_file))?;
+	let data = self::read_data(&cmd_args.input_file)
+		.with_context(|| format!("Unable to read data file: {:?}", cmd_args.input_file))?;
+	anyhow::ensure!(
+		!data.hemem.epochs.is_empty(),
+		"Data had no epochs, set `hemem.epoch_length` in the config"
+	);
+
+	// Calculate the bandwidth of each memory on each epoch
+	// Note: Trace time is in nanoseconds, so bytes per nanosecond are GB/s
+	let bandwidths = data
+		.hemem
+		.epochs
+		.iter()
+		.flat_map(|epoch| {
+			let time = self::date_normalized(epoch.end_time - 1, &data);
+			let duration = (epoch.end_time - epoch.start_time) as f64;
+			epoch.memories.iter().enumerate().map(move |(mem_idx, epoch_mem)| {
+				let bandwidth = (epoch_mem.read_bytes + epoch_mem.write_bytes) as f64 / duration;
+				(mem_idx, (time, bandwidth))
+			})
+		})
+		.into_group_map()
+		.into_iter()
+		.collect::<BTreeMap<_, _>>();
+
+	// Finally create the plot
+	let mut fg = gnuplot::Figure::new();
+	let fg_axes2d = fg.axes2d();
+
+	let mem_names = self::memory_names(&config);
+	for (mem_idx, bandwidths) in bandwidths {
+		let color_progress = 1.0 - mem_idx as f64 / (mem_names.len() as f64 - 1.0);
+		let color = LinSrgb::new(1.0, 0.0, 0.0).mix(LinSrgb::new(0.0, 1.0, 0.0), color_progress);
+		let color = format!("#{:x}", color.into_format::<u8>());
+
+		let mem_name = mem_names
+			.get(mem_idx)
+			.expect("Config had less memories than input file");
+
+		fg_axes2d.lines(
+			bandwidths.iter().map(|(time, _)| time),
+			bandwidths.iter().map(|(_, bandwidth)| bandwidth),
+			&[
+				PlotOption::Caption(&format!("Memory {mem_idx:?} ({mem_name})")),
+				PlotOption::Color(&color),
+				PlotOption::LineWidth(cmd_args.line_width),
+			],
+		);
+	}
+
+	fg_axes2d
+		.set_x_label("Time (normalized)", &[])
+		.set_y_label("Bandwidth (GB/s)", &[])
+		.set_x_range(AutoOption::Fix(0.0), AutoOption::Fix(1.0))
+		.set_y_range(AutoOption::Fix(0.0), AutoOption::Auto);
+
+	// Then output the plot
+	self::handle_output(&cmd_args.output, &mut fg).context("Unable to handle output")?;
+
+	Ok(())
+}
+
+/// Calculates the occupancies of each memory over time from the epochs of `data`
+fn memory_occupancies_from_epochs(data: &ftmemsim::data::Data) -> BTreeMap<usize, Vec<(f64, usize)>> {
+	data.hemem
+		.epochs
+		.iter()
+		.flat_map(|epoch| {
+			let time = self::date_normalized(epoch.end_time - 1, data);
+			epoch
+				.memories
+				.iter()
+				.enumerate()
+				.map(move |(mem_idx, epoch_mem)| (mem_idx, (time, epoch_mem.occupancy)))
+		})
+		.into_group_map()
+		.into_iter()
+		.collect()
+}
+
+/// Calculates the occupancies of each memory over time by replaying all migrations of `data`
+fn memory_occupancies_from_migrations(
+	config: &ftmemsim::config::Config,
+	data: &ftmemsim::data::Data,
+) -> BTreeMap<usize, Vec<(f64, usize)>> {
+	let mut memories_occupancy = (0..self::memory_names(config).len())
 		.map(|mem_idx| (mem_idx, 0_usize))
 		.collect::<BTreeMap<_, _>>();
 
 	// Note: We sort and group all migrations by time, so we can process each "time step"
 	//       all at once, then only output the final occupancies. This is to avoid having
 	//       occupancies go above the capacity temporarily within one time step.
-	let occupancies = data
-		.hemem
+	data.hemem
 		.page_migrations
 		.migrations
 		.values()
@@ -476,7 +628,7 @@ fn draw_memory_occupancy(cmd_args: args::MemoryOccupancy) -> Result<(), anyhow::
 					.checked_add_signed(delta)
 					.expect("Memory occupancy was negative / above `usize::MAX`");
 
-				(mem_idx, (self::date_normalized(time, &data), *occupancy))
+				(mem_idx, (self::date_normalized(time, data), *occupancy))
 			};
 
 			// Process all migrations in this time step
@@ -497,44 +649,7 @@ fn draw_memory_occupancy(cmd_args: args::MemoryOccupancy) -> Result<(), anyhow::
 		})
 		.into_group_map()
 		.into_iter()
-		.collect::<BTreeMap<_, _>>();
-
-	// Finally create the plot
-	let mut fg = gnuplot::Figure::new();
-	let fg_axes2d = fg.axes2d();
-
-	for (mem_idx, occupancies) in occupancies {
-		let color_progress = 1.0 - mem_idx as f64 / (config.hemem.memories.len() as f64 - 1.0);
-		let color = LinSrgb::new(1.0, 0.0, 0.0).mix(LinSrgb::new(0.0, 1.0, 0.0), color_progress);
-		let color = format!("#{:x}", color.into_format::<u8>());
-
-		let mem = config
-			.hemem
-			.memories
-			.get(mem_idx)
-			.expect("Config had less memories than input file");
-
-		fg_axes2d.lines(
-			occupancies.iter().map(|(time, _)| time),
-			occupancies.iter().map(|(_, occupancy)| occupancy),
-			&[
-				PlotOption::Caption(&format!("Memory {mem_idx:?} ({})", mem.name)),
-				PlotOption::Color(&color),
-				PlotOption::LineWidth(cmd_args.line_width),
-			],
-		);
-	}
-
-	fg_axes2d
-		.set_x_label("Time (normalized)", &[])
-		.set_y_label("Page occupancy", &[])
-		.set_x_range(AutoOption::Fix(0.0), AutoOption::Fix(1.0))
-		.set_y_range(AutoOption::Fix(0.0), AutoOption::Auto);
-
-	// Then output the plot
-	self::handle_output(&cmd_args.output, &mut fg).context("Unable to handle output")?;
-
-	Ok(())
+		.collect::<BTreeMap<_, _>>()
 }
 
 /// Calculates a date normalized
@@ -571,6 +686,19 @@ fn page_migrations_hist_data(data: &ftmemsim::data::Data) -> Vec<usize> {
 		.collect::<Vec<_>>()
 }
 
+/// Returns the names of all memories in `config`, by their index.
+///
+/// Includes the swap memory, if any, after all other memories.
+fn memory_names(config: &ftmemsim::config::Config) -> Vec<&str> {
+	config
+		.hemem
+		.memories
+		.iter()
+		.map(|mem| mem.name.as_str())
+		.chain(config.hemem.swap.as_ref().map(|_| "swap"))
+		.collect()
+}
+
 /// Reads config from `config_file`
 fn read_config(config_file: &Path) -> Result<ftmemsim::config::Config, anyhow::Error> {
 	// Open the file
@@ -658,3 +786,23 @@ fn page_ptr_idxs(data: &ftmemsim::data::Data) -> BTreeMap<u64, usize> {
 		.map(|(idx, (&page_ptr, _))| (page_ptr, idx))
 		.collect::<BTreeMap<_, _>>()
 }
+
+/// Returns the page accesses of `data`.
+///
+/// # Errors
+/// Returns an error if `data` has no access log.
+fn page_accesses(data: &ftmemsim::data::Data) -> Result<&[ftmemsim::data::PageAccess], anyhow::Error> {
+	data.hemem
+		.page_accesses
+		.as_ref()
+		.map(|page_accesses| &*page_accesses.accesses)
+		.context("Data had no access log, don't disable `hemem.access_log` in the config")
+}
+
+/// Returns the axis label of the page temperatures in `data`, with their definition, if any
+fn temperature_label(data: &ftmemsim::data::Data) -> String {
+	match &data.hemem.temperature {
+		Some(temperature) => format!("Temperature ({temperature})"),
+		None => "Temperature".to_owned(),
+	}
+}
@@ -1,10 +1,13 @@
 //! Duration
 
 // Imports
-use std::{fmt, writeln};
+use std::{
+	fmt,
+	ops::{Add, AddAssign},
+};
 
 /// Duration with femto-second precision
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
 pub struct FemtoDuration {
 	/// Whole seconds
 	secs: u64,
@@ -16,10 +19,15 @@ pub struct FemtoDuration {
 impl FemtoDuration {
 	/// Number of femto-seconds per nano-second
 	pub const FEMTOS_PER_NANO: u64 = 1_000_000;
+	/// Number of femto-seconds per second
+	pub const FEMTOS_PER_SEC: u64 = 1_000_000_000_000_000;
 	/// Number of nano-seconds per second
 	pub const NANOS_PER_SEC: u64 = 1_000_000_000;
-	/// Number of femto-seconds per second
-	pub const _FEMTOS_PER_SEC: u64 = 1_000_000_000_000_000;
+	/// Zero duration
+	pub const ZERO: Self = Self {
+		secs:       0,
+		femto_secs: 0,
+	};
 
 	/// Creates a new duration from floating-point nanoseconds
 	// TODO: Deal with rounding better?
@@ -29,6 +37,29 @@ impl FemtoDuration {
 
 		Self { secs, femto_secs }
 	}
+
+	/// Returns this duration as floating-point nanoseconds
+	pub fn as_nanos_f64(self) -> f64 {
+		self.secs as f64 * Self::NANOS_PER_SEC as f64 + self.femto_secs as f64 / Self::FEMTOS_PER_NANO as f64
+	}
+}
+
+impl Add for FemtoDuration {
+	type Output = Self;
+
+	fn add(self, rhs: Self) -> Self::Output {
+		let femto_secs = self.femto_secs + rhs.femto_secs;
+		Self {
+			secs:       self.secs + rhs.secs + femto_secs / Self::FEMTOS_PER_SEC,
+			femto_secs: femto_secs % Self::FEMTOS_PER_SEC,
+		}
+	}
+}
+
+impl AddAssign for FemtoDuration {
+	fn add_assign(&mut self, rhs: Self) {
+		*self = *self + rhs;
+	}
 }
 
 
@@ -46,17 +77,23 @@ impl fmt::Display for FemtoDuration {
 
 		match (hours, mins, secs, millis, micros, nanos, picos, femtos) {
 			// If we have no hours, mins or secs, format in the smallest unit
-			(0, 0, 0, 0, 0, 0, 0, 0) => (),
-			(0, 0, 0, 0, 0, 0, 0, _) => writeln!(f, "{femtos}fs")?,
-			(0, 0, 0, 0, 0, 0, ..) => writeln!(f, "{picos}.{femtos}ps")?,
-			(0, 0, 0, 0, 0, ..) => writeln!(f, "{nanos}.{picos}{femtos}ns")?,
-			(0, 0, 0, 0, ..) => writeln!(f, "{micros}.{nanos}{picos}{femtos}µs")?,
-			(0, 0, 0, ..) => writeln!(f, "{millis}.{micros}{nanos}{picos}{femtos}ms")?,
+			(0, 0, 0, 0, 0, 0, 0, 0) => write!(f, "0s")?,
+			(0, 0, 0, 0, 0, 0, 0, _) => write!(f, "{femtos}fs")?,
+			(0, 0, 0, 0, 0, 0, ..) => write!(f, "{picos}.{femtos:03}ps")?,
+			(0, 0, 0, 0, 0, ..) => write!(f, "{nanos}.{picos:03}{femtos:03}ns")?,
+			(0, 0, 0, 0, ..) => write!(f, "{micros}.{nanos:03}{picos:03}{femtos:03}µs")?,
+			(0, 0, 0, ..) => write!(f, "{millis}.{micros:03}{nanos:03}{picos:03}{femtos:03}ms")?,
 
 			// Else format it as the decimal part
-			(0, 0, ..) => writeln!(f, "{secs}.{millis}{micros}{nanos}{picos}{femtos}s")?,
-			(0, ..) => writeln!(f, "{mins}m{secs}.{millis}{micros}{nanos}{picos}{femtos}s")?,
-			(..) => writeln!(f, "{hours}h{mins}m{secs}.{millis}{micros}{nanos}{picos}{femtos}s")?,
+			(0, 0, ..) => write!(f, "{secs}.{millis:03}{micros:03}{nanos:03}{picos:03}{femtos:03}s")?,
+			(0, ..) => write!(
+				f,
+				"{mins}m{secs}.{millis:03}{micros:03}{nanos:03}{picos:03}{femtos:03}s"
+			)?,
+			(..) => write!(
+				f,
+				"{hours}h{mins}m{secs}.{millis:03}{micros:03}{nanos:03}{picos:03}{femtos:03}s"
+			)?,
 		}
 
 		Ok(())
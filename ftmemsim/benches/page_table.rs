@@ -0,0 +1,206 @@
+//! Page table benchmarks
+//!
+//! Measures the page table, and hemem on top of it, on synthetic traces
+//! with large footprints.
+
+// Features
+#![feature(test)]
+
+// Imports
+extern crate test;
+use {
+	ftmemsim::{
+		classifiers::hemem::{self, memories::MemIdx, Page, PagePtr, PageTable},
+		pin_trace,
+		sim::{self, Classifier},
+	},
+	ftmemsim_util::FemtoDuration,
+	rand::{rngs::StdRng, Rng, SeedableRng},
+	test::Bencher,
+};
+
+/// Pages in the footprint of the page table benchmarks (`4GiB`)
+const TABLE_PAGES: u64 = 1 << 20;
+
+/// Pages in the footprint of the hemem benchmarks (`1GiB`)
+const HEMEM_PAGES: u64 = 1 << 18;
+
+/// Page capacity of the fastest memory in the hemem benchmarks
+const HEMEM_FAST_PAGES: usize = 1 << 14;
+
+/// Records in each synthetic trace
+const TRACE_LEN: usize = 1 << 16;
+
+/// Base address of the footprint
+const BASE_ADDR: u64 = 0x7f00_0000_0000;
+
+#[bench]
+fn page_table_lookup(bencher: &mut Bencher) {
+	let mem_idxs = self::mem_idxs();
+	let mut page_table = self::page_table(&mem_idxs);
+	let page_ptrs = self::uniform_trace(TABLE_PAGES, TRACE_LEN)
+		.map(|record| PagePtr::new(record.addr))
+		.collect::<Vec<_>>();
+
+	bencher.iter(|| {
+		for &page_ptr in &page_ptrs {
+			let page = page_table.get_mut(page_ptr).expect("Page wasn't in page table");
+			page.register_read_access(0);
+		}
+	});
+}
+
+#[bench]
+fn page_table_move(bencher: &mut Bencher) {
+	let mem_idxs = self::mem_idxs();
+	let mut page_table = self::page_table(&mem_idxs);
+	let page_ptrs = self::uniform_trace(TABLE_PAGES, TRACE_LEN)
+		.map(|record| PagePtr::new(record.addr))
+		.collect::<Vec<_>>();
+
+	bencher.iter(|| {
+		for (idx, &page_ptr) in page_ptrs.iter().enumerate() {
+			page_table.move_mem(page_ptr, mem_idxs[idx % mem_idxs.len()]);
+		}
+	});
+}
+
+#[bench]
+fn hemem_uniform(bencher: &mut Bencher) {
+	let trace = self::uniform_trace(HEMEM_PAGES, TRACE_LEN).collect::<Vec<_>>();
+	bencher.iter(|| self::run_hemem(&trace, 8, 4, 18));
+}
+
+#[bench]
+fn hemem_hot_set(bencher: &mut Bencher) {
+	let trace = self::hot_set_trace(HEMEM_PAGES, TRACE_LEN).collect::<Vec<_>>();
+	bencher.iter(|| self::run_hemem(&trace, 8, 4, 18));
+}
+
+#[bench]
+fn hemem_cooling(bencher: &mut Bencher) {
+	// Note: With such low thresholds, the hot set outgrows the fastest memory, so
+	//       it stays full and is cooled on almost every promotion.
+	let trace = self::hot_set_trace(HEMEM_PAGES, TRACE_LEN).collect::<Vec<_>>();
+	bencher.iter(|| self::run_hemem(&trace, 2, 1, 4));
+}
+
+/// Runs hemem over `trace`, with a fastest memory smaller than it's footprint
+fn run_hemem(
+	trace: &[pin_trace::Record],
+	read_hot_threshold: usize,
+	write_hot_threshold: usize,
+	global_cooling_threshold: usize,
+) {
+	let mut hemem = hemem::HeMem::new(
+		hemem::Config {
+			read_hot_threshold,
+			write_hot_threshold,
+			global_cooling_threshold,
+			hotness: hemem::Hotness::default(),
+			temperature_weights: hemem::TemperatureWeights::default(),
+			cold_page_selection: hemem::ColdPageSelection::default(),
+			demotion_batch: 1,
+			epoch_length: None,
+			access_log: true,
+			oom_behavior: hemem::OomBehavior::Error,
+			bandwidth_window: 1000,
+		},
+		vec![
+			self::memory("ram", HEMEM_FAST_PAGES),
+			self::memory("optane", HEMEM_PAGES as usize),
+		],
+		None,
+		None,
+		None,
+	)
+	.expect("Unable to create hemem");
+
+	for &record in trace {
+		hemem
+			.handle_trace(sim::Trace { record })
+			.expect("Unable to handle trace");
+	}
+}
+
+/// Returns a page table with `TABLE_PAGES` pages, spread over `mem_idxs`
+fn page_table(mem_idxs: &[MemIdx]) -> PageTable {
+	let mut page_table = PageTable::new();
+	for page_idx in 0..TABLE_PAGES {
+		let page_ptr = PagePtr::new(BASE_ADDR + page_idx * 4096);
+		let mem_idx = mem_idxs[page_idx as usize % mem_idxs.len()];
+		page_table
+			.insert(Page::new(page_ptr, mem_idx, hemem::Hotness::default()))
+			.expect("Unable to insert page");
+	}
+
+	page_table
+}
+
+/// Returns the indexes of 2 memories
+fn mem_idxs() -> Vec<MemIdx> {
+	hemem::Memories::new(
+		[self::memory("ram", usize::MAX), self::memory("optane", usize::MAX)],
+		None,
+	)
+	.expect("Unable to create memories")
+	.iter()
+	.map(|(mem_idx, _)| mem_idx)
+	.collect()
+}
+
+/// Creates a memory with `page_capacity` pages
+fn memory(name: &str, page_capacity: usize) -> hemem::Memory {
+	hemem::Memory::new(
+		name,
+		page_capacity,
+		hemem::memories::AccessLatencies {
+			read:  FemtoDuration::from_nanos_f64(100.0),
+			write: FemtoDuration::from_nanos_f64(100.0),
+			fault: FemtoDuration::from_nanos_f64(1000.0),
+		},
+		hemem::memories::Targets::default(),
+		hemem::memories::Bandwidths::default(),
+	)
+}
+
+/// Returns a trace of `len` records uniformly spread over `pages` pages
+fn uniform_trace(pages: u64, len: usize) -> impl Iterator<Item = pin_trace::Record> {
+	let mut rng = StdRng::seed_from_u64(0);
+	(0..len).map(move |idx| {
+		let page_idx = self::rng_page(&mut rng, pages);
+		self::record(&mut rng, idx, page_idx)
+	})
+}
+
+/// Returns a trace of `len` records over `pages` pages, with 90% of them
+/// going to the first 10% of pages
+fn hot_set_trace(pages: u64, len: usize) -> impl Iterator<Item = pin_trace::Record> {
+	let mut rng = StdRng::seed_from_u64(0);
+	(0..len).map(move |idx| {
+		let page_idx = match rng.gen_bool(0.9) {
+			true => self::rng_page(&mut rng, pages / 10),
+			false => self::rng_page(&mut rng, pages),
+		};
+		self::record(&mut rng, idx, page_idx)
+	})
+}
+
+/// Returns a random page index within `pages`
+fn rng_page(rng: &mut StdRng, pages: u64) -> u64 {
+	rng.gen_range(0..pages)
+}
+
+/// Returns the `idx`-th record of a trace, accessing page `page_idx`.
+///
+/// A quarter of the accesses are writes.
+fn record(rng: &mut StdRng, idx: usize, page_idx: u64) -> pin_trace::Record {
+	pin_trace::Record {
+		time: idx as u64,
+		addr: BASE_ADDR + page_idx * 4096,
+		kind: match rng.gen_bool(0.25) {
+			true => pin_trace::RecordAccessKind::Write,
+			false => pin_trace::RecordAccessKind::Read,
+		},
+	}
+}
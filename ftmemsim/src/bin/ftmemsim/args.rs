@@ -1,31 +1,120 @@
 //! Arguments
 
 // Imports
-use std::path::PathBuf;
+use {
+	crate::sweep::SweepAxis,
+	std::{os::fd::RawFd, path::PathBuf},
+};
 
 /// Arguments
 #[derive(Debug)]
 #[derive(clap::Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
 	/// Log file
 	///
 	/// Specifies a file to perform verbose logging to.
 	/// You can use `RUST_LOG_FILE` to set filtering options
-	#[clap(long = "log-file")]
+	#[clap(long = "log-file", global = true)]
 	pub log_file: Option<PathBuf>,
 
 	/// Whether to append to the log file
-	#[clap(long = "log-file-append")]
+	#[clap(long = "log-file-append", global = true)]
 	pub log_file_append: bool,
 
+	/// Sub-command
+	#[command(subcommand)]
+	pub sub_cmd: Option<SubCmd>,
+
+	/// Run arguments, when no sub-command is given
+	#[command(flatten)]
+	pub run: Option<Run>,
+}
+
+/// Sub-command
+#[derive(Debug, clap::Subcommand)]
+pub enum SubCmd {
+	#[clap(name = "sweep")]
+	Sweep(Sweep),
+}
+
+/// Simulates a trace with one or more configs
+#[derive(Debug, clap::Args)]
+pub struct Run {
 	/// Trace file
 	pub trace_file: PathBuf,
 
-	/// Config file
-	#[clap(long = "config")]
-	pub config_file: PathBuf,
+	/// Config files
+	///
+	/// May be specified multiple times, in which case each config
+	/// is simulated on the same pass over the trace file.
+	#[clap(long = "config", required = true)]
+	pub config_files: Vec<PathBuf>,
 
-	/// Output file
+	/// Output files
+	///
+	/// If specified, must be given once per config file, in the same order.
 	#[clap(long = "output")]
-	pub output_file: Option<PathBuf>,
+	pub output_files: Vec<PathBuf>,
+
+	/// Summary json file
+	///
+	/// If specified, the summary of each config is also written to it as json.
+	#[clap(long = "summary-json")]
+	pub summary_json_file: Option<PathBuf>,
+
+	/// Progress json file descriptor
+	///
+	/// If specified, progress events are written to it as json lines.
+	/// The file descriptor must be open, must not be stdio, and is owned
+	/// (and closed) by us afterwards.
+	#[clap(long = "progress-json")]
+	pub progress_json_fd: Option<RawFd>,
+}
+
+/// Simulates a trace with all combinations of parameters of a base config
+#[derive(Debug, clap::Args)]
+pub struct Sweep {
+	/// Trace file
+	pub trace_file: PathBuf,
+
+	/// Base config file
+	#[clap(long = "base-config")]
+	pub base_config_file: PathBuf,
+
+	/// Sweep axes
+	///
+	/// Each axis has the form `<path>=<values>`, where `<path>` is a path into the
+	/// config, such as `hemem.memories[0].page_capacity`, and `<values>` is either a
+	/// range, such as `200..4500 step 250` (or `200..=4500` to include the end), or a
+	/// list, such as `[4, 8, 16]`.
+	#[clap(long = "axis")]
+	pub axes: Vec<SweepAxis>,
+
+	/// Output directory
+	///
+	/// Receives the config and output of each combination, as well as a `manifest.json`
+	/// tying them to their parameter values.
+	#[clap(long = "output-dir")]
+	pub output_dir: PathBuf,
+
+	/// Maximum combinations simulated at once.
+	///
+	/// If unspecified, defaults to the available parallelism.
+	#[clap(long = "jobs")]
+	pub jobs: Option<usize>,
+
+	/// Summary json file
+	///
+	/// If specified, the summary of each config is also written to it as json.
+	#[clap(long = "summary-json")]
+	pub summary_json_file: Option<PathBuf>,
+
+	/// Progress json file descriptor
+	///
+	/// If specified, progress events are written to it as json lines.
+	/// The file descriptor must be open, must not be stdio, and is owned
+	/// (and closed) by us afterwards.
+	#[clap(long = "progress-json")]
+	pub progress_json_fd: Option<RawFd>,
 }
@@ -0,0 +1,111 @@
+//! Classifier
+
+// Imports
+use {
+	ftmemsim::{
+		classifiers::{arc, autonuma, hemem, memtis, mglru, observer::Observer, placement, tpp},
+		sim,
+	},
+	std::fmt,
+};
+
+/// Classifier
+#[derive(Debug)]
+pub enum Classifier {
+	HeMem(hemem::HeMem),
+	Tpp(tpp::Tpp),
+	AutoNuma(autonuma::AutoNuma),
+	Memtis(memtis::Memtis),
+	Mglru(mglru::Mglru),
+	Placement(placement::Placement),
+	Arc(arc::Arc),
+}
+
+impl Classifier {
+	/// Returns the statistics
+	pub fn statistics(&self) -> &hemem::Statistics {
+		match self {
+			Self::HeMem(hemem) => hemem.statistics(),
+			Self::Tpp(tpp) => tpp.statistics(),
+			Self::AutoNuma(autonuma) => autonuma.statistics(),
+			Self::Memtis(memtis) => memtis.statistics(),
+			Self::Mglru(mglru) => mglru.statistics(),
+			Self::Placement(placement) => placement.statistics(),
+			Self::Arc(arc) => arc.statistics(),
+		}
+	}
+}
+
+impl sim::Classifier for Classifier {
+	fn register_trace(&mut self, trace: sim::Trace) {
+		match self {
+			Self::HeMem(hemem) => hemem.register_trace(trace),
+			Self::Tpp(tpp) => tpp.register_trace(trace),
+			Self::AutoNuma(autonuma) => autonuma.register_trace(trace),
+			Self::Memtis(memtis) => memtis.register_trace(trace),
+			Self::Mglru(mglru) => mglru.register_trace(trace),
+			Self::Placement(placement) => placement.register_trace(trace),
+			Self::Arc(arc) => arc.register_trace(trace),
+		}
+	}
+
+	fn handle_trace(&mut self, trace: sim::Trace) -> Result<(), anyhow::Error> {
+		match self {
+			Self::HeMem(hemem) => hemem.handle_trace(trace),
+			Self::Tpp(tpp) => tpp.handle_trace(trace),
+			Self::AutoNuma(autonuma) => autonuma.handle_trace(trace),
+			Self::Memtis(memtis) => memtis.handle_trace(trace),
+			Self::Mglru(mglru) => mglru.handle_trace(trace),
+			Self::Placement(placement) => placement.handle_trace(trace),
+			Self::Arc(arc) => arc.handle_trace(trace),
+		}
+	}
+
+	fn finish(&mut self) {
+		match self {
+			Self::HeMem(hemem) => hemem.finish(),
+			Self::Tpp(tpp) => tpp.finish(),
+			Self::AutoNuma(autonuma) => autonuma.finish(),
+			Self::Memtis(memtis) => memtis.finish(),
+			Self::Mglru(mglru) => mglru.finish(),
+			Self::Placement(placement) => placement.finish(),
+			Self::Arc(arc) => arc.finish(),
+		}
+	}
+
+	fn add_observer(&mut self, observer: impl Observer + 'static) {
+		match self {
+			Self::HeMem(hemem) => hemem.add_observer(observer),
+			Self::Tpp(tpp) => tpp.add_observer(observer),
+			Self::AutoNuma(autonuma) => autonuma.add_observer(observer),
+			Self::Memtis(memtis) => memtis.add_observer(observer),
+			Self::Mglru(mglru) => mglru.add_observer(observer),
+			Self::Placement(placement) => placement.add_observer(observer),
+			Self::Arc(arc) => arc.add_observer(observer),
+		}
+	}
+
+	fn fmt_debug(&mut self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+		match self {
+			Self::HeMem(hemem) => hemem.fmt_debug(f),
+			Self::Tpp(tpp) => tpp.fmt_debug(f),
+			Self::AutoNuma(autonuma) => autonuma.fmt_debug(f),
+			Self::Memtis(memtis) => memtis.fmt_debug(f),
+			Self::Mglru(mglru) => mglru.fmt_debug(f),
+			Self::Placement(placement) => placement.fmt_debug(f),
+			Self::Arc(arc) => arc.fmt_debug(f),
+		}
+	}
+
+	fn debug_stats(&mut self) -> serde_json::Value {
+		match self {
+			Self::HeMem(hemem) => hemem.debug_stats(),
+			Self::Tpp(tpp) => tpp.debug_stats(),
+			Self::AutoNuma(autonuma) => autonuma.debug_stats(),
+			Self::Memtis(memtis) => memtis.debug_stats(),
+			Self::Mglru(mglru) => mglru.debug_stats(),
+			Self::Placement(placement) => placement.debug_stats(),
+			Self::Arc(arc) => arc.debug_stats(),
+		}
+	}
+}
@@ -5,22 +5,45 @@
 
 // Modules
 mod args;
+mod classifier;
+mod progress;
+mod summary;
+mod sweep;
 
 // Imports
 use {
-	self::args::Args,
+	self::{args::Args, classifier::Classifier, progress::JsonLinesProgress, summary::Summary},
 	anyhow::Context,
 	clap::Parser,
 	ftmemsim::{
-		classifiers::{hemem, hemem::memories::MemIdx},
+		cache,
+		classifiers::{
+			arc,
+			autonuma,
+			hemem::{self, hotness, memories::MemIdx},
+			memtis,
+			mglru,
+			placement,
+			tiered,
+			tpp,
+		},
 		config,
 		data,
+		sim,
 		PinTraceReader,
 		Simulator,
 	},
 	ftmemsim_util::{logger, FemtoDuration},
 	gzp::par::compress::ParCompress,
-	std::{fs, time::Duration},
+	itertools::Itertools,
+	std::{
+		collections::BTreeMap,
+		fs,
+		io,
+		os::fd::{FromRawFd, RawFd},
+		path::{Path, PathBuf},
+		time::Duration,
+	},
 };
 
 fn main() -> Result<(), anyhow::Error> {
@@ -31,100 +54,750 @@ fn main() -> Result<(), anyhow::Error> {
 	// Initialize logging
 	logger::init(args.log_file.as_deref(), args.log_file_append);
 
+	// Then check the sub-command
+	match args.sub_cmd {
+		Some(args::SubCmd::Sweep(cmd_args)) => self::sweep(&cmd_args),
+		None => self::run(&args.run.expect("Run arguments should be present without a sub-command")),
+	}
+}
+
+/// Simulates a trace with one or more configs
+fn run(cmd_args: &args::Run) -> Result<(), anyhow::Error> {
+	// Ensure we got an output file for each config, if any
+	anyhow::ensure!(
+		cmd_args.output_files.is_empty() || cmd_args.output_files.len() == cmd_args.config_files.len(),
+		"Expected {} output files, one per config file, found {}",
+		cmd_args.config_files.len(),
+		cmd_args.output_files.len()
+	);
+
+	// Read all the config files
+	// TODO: Allow not passing it and use a default?
+	let simulations = cmd_args
+		.config_files
+		.iter()
+		.enumerate()
+		.map(|(config_idx, config_file)| {
+			let config = self::read_config(config_file)
+				.with_context(|| format!("Unable to read config file {config_file:?}"))?;
+			Ok::<_, anyhow::Error>(Simulation {
+				config_file: config_file.clone(),
+				config,
+				output_file: cmd_args.output_files.get(config_idx).cloned(),
+			})
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let progress_json_file = self::open_progress_json(cmd_args.progress_json_fd)?;
+	let summaries = self::simulate(&cmd_args.trace_file, &simulations, progress_json_file.as_ref())?;
+	self::report_summaries(&summaries, cmd_args.summary_json_file.as_deref())
+}
+
+/// Simulates a trace with all combinations of parameters of a base config
+fn sweep(cmd_args: &args::Sweep) -> Result<(), anyhow::Error> {
+	// Read the base config
+	let base_config = {
+		let base_config_file = fs::File::open(&cmd_args.base_config_file).context("Unable to open base config file")?;
+		serde_json::from_reader::<_, serde_json::Value>(base_config_file).context("Unable to parse base config file")?
+	};
+
+	// Then create the config for each combination
+	let combinations = sweep::combinations(&cmd_args.axes);
+	tracing::info!("Sweeping {} combinations", combinations.len());
+	fs::create_dir_all(&cmd_args.output_dir).context("Unable to create output directory")?;
+
+	let mut simulations = vec![];
+	let mut manifest = sweep::Manifest {
+		trace_file:       cmd_args.trace_file.clone(),
+		base_config_file: cmd_args.base_config_file.clone(),
+		runs:             vec![],
+	};
+	for (combination_idx, values) in combinations.into_iter().enumerate() {
+		let mut config = base_config.clone();
+		let mut params = BTreeMap::new();
+		for (axis, value) in cmd_args.axes.iter().zip(values) {
+			sweep::set_param(&mut config, &axis.path, value.clone())
+				.with_context(|| format!("Unable to set {:?} in base config", axis.path))?;
+			params.insert(axis.path.clone(), value);
+		}
+
+		// Note: We write the config so it may be passed to the graphs later
+		let config_file = PathBuf::from(format!("{combination_idx}.json"));
+		let output_file = PathBuf::from(format!("{combination_idx}.bin.gz"));
+		{
+			let file =
+				fs::File::create(cmd_args.output_dir.join(&config_file)).context("Unable to create config file")?;
+			serde_json::to_writer_pretty(file, &config).context("Unable to write config file")?;
+		}
+
+		let config = serde_json::from_value::<config::Config>(config)
+			.with_context(|| format!("Unable to parse config for parameters {params:?}"))?;
+		simulations.push(Simulation {
+			config_file: cmd_args.output_dir.join(&config_file),
+			config,
+			output_file: Some(cmd_args.output_dir.join(&output_file)),
+		});
+		manifest.runs.push(sweep::ManifestRun {
+			config_file,
+			output_file,
+			params,
+		});
+	}
+
+	// Write the manifest before simulating, so it's clear which output is which while running
+	{
+		let manifest_file =
+			fs::File::create(cmd_args.output_dir.join("manifest.json")).context("Unable to create manifest file")?;
+		serde_json::to_writer_pretty(manifest_file, &manifest).context("Unable to write manifest file")?;
+	}
+
+	// Note: Each simulation keeps all of it's statistics in memory until the end, so we
+	//       only run `jobs` of them at a time.
+	let jobs = match cmd_args.jobs {
+		Some(jobs) => jobs,
+		None => std::thread::available_parallelism()
+			.context("Unable to get available parallelism")?
+			.get(),
+	};
+	anyhow::ensure!(jobs != 0, "Jobs must not be 0");
+	let progress_json_file = self::open_progress_json(cmd_args.progress_json_fd)?;
+	let mut summaries = vec![];
+	for (chunk_idx, simulations) in simulations.chunks(jobs).enumerate() {
+		let start_idx = chunk_idx * jobs;
+		tracing::info!(
+			"Simulating combinations {start_idx}..{} of {}",
+			start_idx + simulations.len(),
+			manifest.runs.len()
+		);
+		summaries.extend(self::simulate(
+			&cmd_args.trace_file,
+			simulations,
+			progress_json_file.as_ref(),
+		)?);
+	}
+
+	self::report_summaries(&summaries, cmd_args.summary_json_file.as_deref())
+}
+
+/// Simulation of a single config
+#[derive(Debug)]
+struct Simulation {
+	/// Config file
+	config_file: PathBuf,
+
+	/// Config
+	config: config::Config,
+
+	/// Output file
+	output_file: Option<PathBuf>,
+}
+
+/// Opens the progress json file descriptor `progress_json_fd`, if any
+fn open_progress_json(progress_json_fd: Option<RawFd>) -> Result<Option<fs::File>, anyhow::Error> {
+	let Some(progress_json_fd) = progress_json_fd else {
+		return Ok(None);
+	};
+
+	// Note: Stdio is used for logging and we'd close it when dropping the file.
+	anyhow::ensure!(
+		progress_json_fd > 2,
+		"Progress json file descriptor {progress_json_fd} must not be stdin, stdout or stderr"
+	);
+
+	// SAFETY: `F_GETFD` only queries the flags of the file descriptor, and fails
+	//         with `EBADF` if it isn't open.
+	if unsafe { libc::fcntl(progress_json_fd, libc::F_GETFD) } == -1 {
+		return Err(io::Error::last_os_error())
+			.with_context(|| format!("Progress json file descriptor {progress_json_fd} isn't open"));
+	}
+
+	// SAFETY: We checked above that the file descriptor is open. It was passed by
+	//         our parent, which hands ownership of it over to us. We take care of
+	//         closing it and nothing else in this process uses it.
+	let progress_json_file = unsafe { fs::File::from_raw_fd(progress_json_fd) };
+	Ok(Some(progress_json_file))
+}
+
+/// Simulates `trace_file` with all `simulations`, returning the summary of each.
+///
+/// Progress events are written to `progress_json_file`, if any.
+fn simulate(
+	trace_file: &Path,
+	simulations: &[Simulation],
+	progress_json_file: Option<&fs::File>,
+) -> Result<Vec<Summary>, anyhow::Error> {
 	// Read the trace file
-	let mut pin_trace_file = fs::File::open(&args.trace_file).context("Unable to open trace file")?;
+	let mut pin_trace_file = fs::File::open(trace_file).context("Unable to open trace file")?;
 	let mut pin_trace_reader = PinTraceReader::from_reader(&mut pin_trace_file).context("Unable to parse pin trace")?;
 	tracing::trace!(target: "ftmemsim::parse_pin_trace", ?pin_trace_reader, "Parsed pin trace");
 
-	// Read the config file
-	// TODO: Allow not passing it and use a default?
-	let config = {
-		let config_file = fs::File::open(&args.config_file).context("Unable to open config file")?;
-		serde_json::from_reader::<_, self::config::Config>(config_file).context("Unable to parse config file")?
-	};
+	let debug_output_period_secs = simulations
+		.iter()
+		.map(|simulation| simulation.config.debug_output_period_secs)
+		.min_by(f64::total_cmp)
+		.context("Expected at least 1 config")?;
 
 	// Run the simulator
-	let mut sim = Simulator::new(
-		config.trace_skip,
-		Duration::from_secs_f64(config.debug_output_period_secs),
-	);
-	let mut hemem = hemem::HeMem::new(
-		hemem::Config {
-			read_hot_threshold:       config.hemem.read_hot_threshold,
-			write_hot_threshold:      config.hemem.write_hot_threshold,
-			global_cooling_threshold: config.hemem.global_cooling_threshold,
+	let mut sim = Simulator::new(Duration::from_secs_f64(debug_output_period_secs));
+	if let Some(progress_json_file) = progress_json_file {
+		let progress_json_file = progress_json_file
+			.try_clone()
+			.context("Unable to duplicate progress json file")?;
+		sim.add_progress_observer(JsonLinesProgress::new(progress_json_file));
+	}
+	let mut classifiers = simulations
+		.iter()
+		.map(|simulation| {
+			let classifier = self::create_classifier(&simulation.config)
+				.with_context(|| format!("Unable to create classifier for config {:?}", simulation.config_file))?;
+			let caches = self::create_caches(&simulation.config)
+				.with_context(|| format!("Unable to create caches for config {:?}", simulation.config_file))?;
+			Ok::<_, anyhow::Error>(cache::CacheFilter::new(caches, classifier))
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+	let trace_skips = simulations
+		.iter()
+		.map(|simulation| simulation.config.trace_skip)
+		.collect::<Vec<_>>();
+	let sim_run_output = sim
+		.run(&mut pin_trace_reader, &mut classifiers, &trace_skips)
+		.context("Unable to run simulator")?;
+
+	// Then write all outputs
+	for (simulation, classifier) in simulations.iter().zip(&classifiers) {
+		if let Some(output_file) = &simulation.output_file {
+			// Note: Only hemem weighs the temperatures by the accesses
+			let temperature = match simulation.config.classifier {
+				config::ClassifierConfig::HeMem => Some(data::Temperature {
+					read_weight:  simulation.config.hemem.temperature.read_weight,
+					write_weight: simulation.config.hemem.temperature.write_weight,
+				}),
+				_ => None,
+			};
+			self::write_output(
+				output_file,
+				&sim_run_output,
+				classifier.inner().statistics(),
+				temperature,
+			)
+			.with_context(|| format!("Unable to write output file {output_file:?}"))?;
+		}
+	}
+
+	// Finally create the summaries
+	let summaries = simulations
+		.iter()
+		.zip(&classifiers)
+		.zip(&sim_run_output.traces)
+		.map(|((simulation, classifier), &traces)| {
+			Summary::new(
+				simulation.config_file.clone(),
+				&simulation.config,
+				&sim_run_output,
+				traces,
+				classifier.caches(),
+				classifier.inner().statistics(),
+			)
+		})
+		.collect();
+
+	Ok(summaries)
+}
+
+/// Prints all `summaries`, also writing them to `summary_json_file`, if any
+fn report_summaries(summaries: &[Summary], summary_json_file: Option<&Path>) -> Result<(), anyhow::Error> {
+	for summary in summaries {
+		println!("{summary}");
+	}
+	if let Some(summary_json_file) = summary_json_file {
+		let summary_json_file = fs::File::create(summary_json_file).context("Unable to create summary file")?;
+		serde_json::to_writer_pretty(summary_json_file, summaries).context("Unable to write summary file")?;
+	}
+
+	Ok(())
+}
+
+/// Reads the config from `config_file`
+fn read_config(config_file: &Path) -> Result<config::Config, anyhow::Error> {
+	let config_file = fs::File::open(config_file).context("Unable to open config file")?;
+	serde_json::from_reader::<_, config::Config>(config_file).context("Unable to parse config file")
+}
+
+/// Creates the cpu caches from `config`
+fn create_caches(config: &config::Config) -> Result<Vec<cache::Cache>, anyhow::Error> {
+	let caches = config
+		.caches
+		.iter()
+		.map(|cache_config| {
+			cache::Cache::new(
+				cache_config.name.clone(),
+				cache_config.size_bytes,
+				cache_config.associativity,
+				cache_config.line_bytes.unwrap_or(cache::DEFAULT_LINE_SIZE),
+			)
+			.with_context(|| format!("Unable to create cache {:?}", cache_config.name))
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	// Note: Each level fills and writes back whole lines of the previous, which
+	//       a level with smaller lines would only partially hold.
+	for (prev_cache, cache) in caches.iter().tuple_windows() {
+		anyhow::ensure!(
+			cache.line_size() >= prev_cache.line_size(),
+			"Cache {:?} must not have smaller lines than the previous level, {:?}",
+			cache.name(),
+			prev_cache.name()
+		);
+	}
+
+	Ok(caches)
+}
+
+/// Creates the classifier from `config`
+fn create_classifier(config: &config::Config) -> Result<Classifier, anyhow::Error> {
+	let classifier = match &config.classifier {
+		config::ClassifierConfig::HeMem => Classifier::HeMem(self::create_hemem(config)?),
+		config::ClassifierConfig::Tpp(tpp) => {
+			let tiered = self::create_tiered(config)?;
+			let tpp = tpp::Tpp::new(
+				tpp::Config {
+					low_watermark:      tpp.low_watermark,
+					high_watermark:     tpp.high_watermark,
+					reclaim_scan_pages: tpp.reclaim_scan_pages,
+					scan_period:        tpp.scan_period,
+					scan_pages:         tpp.scan_pages,
+					hint_fault_latency: FemtoDuration::from_nanos_f64(tpp.hint_fault_latency_ns),
+				},
+				tiered,
+			)
+			.context("Unable to create tpp")?;
+			Classifier::Tpp(tpp)
 		},
-		config
-			.hemem
-			.memories
-			.iter()
-			.map(|mem| {
-				hemem::Memory::new(&mem.name, mem.page_capacity, hemem::memories::AccessLatencies {
-					read:  FemtoDuration::from_nanos_f64(mem.read_latency_ns),
-					write: FemtoDuration::from_nanos_f64(mem.write_latency_ns),
-					fault: FemtoDuration::from_nanos_f64(mem.fault_latency_ns),
+		config::ClassifierConfig::AutoNuma(autonuma) => {
+			let tiered = self::create_tiered(config)?;
+			let autonuma = autonuma::AutoNuma::new(
+				autonuma::Config {
+					scan_period:                   autonuma.scan_period,
+					scan_pages:                    autonuma.scan_pages,
+					hint_fault_latency:            FemtoDuration::from_nanos_f64(autonuma.hint_fault_latency_ns),
+					hot_threshold:                 autonuma.hot_threshold,
+					promote_rate_limit_mb_per_sec: autonuma.promote_rate_limit_mb_per_sec,
+				},
+				tiered,
+			)
+			.context("Unable to create autonuma")?;
+			Classifier::AutoNuma(autonuma)
+		},
+		config::ClassifierConfig::Memtis(memtis) => {
+			let tiered = self::create_tiered(config)?;
+			let memtis = memtis::Memtis::new(
+				memtis::Config {
+					adaptation_period: memtis.adaptation_period,
+					cooling_threshold: memtis.cooling_threshold,
+				},
+				tiered,
+			)
+			.context("Unable to create memtis")?;
+			Classifier::Memtis(memtis)
+		},
+		config::ClassifierConfig::Mglru(mglru) => {
+			let tiered = self::create_tiered(config)?;
+			let mglru = mglru::Mglru::new(
+				mglru::Config {
+					generations:    mglru.generations,
+					aging_interval: mglru.aging_interval,
+				},
+				tiered,
+			)
+			.context("Unable to create mglru")?;
+			Classifier::Mglru(mglru)
+		},
+		config::ClassifierConfig::FirstTouch => self::create_placement(config, placement::Config::FirstTouch)?,
+		config::ClassifierConfig::Interleave(interleave) =>
+			self::create_placement(config, placement::Config::Interleave {
+				ratio: interleave.ratio.clone(),
+			})?,
+		config::ClassifierConfig::Random(random) =>
+			self::create_placement(config, placement::Config::Random { seed: random.seed })?,
+		config::ClassifierConfig::Arc => self::create_arc(config, arc::Config::Arc)?,
+		config::ClassifierConfig::TwoQ(two_q) => self::create_arc(config, arc::Config::TwoQ {
+			in_fraction:  two_q.in_fraction,
+			out_fraction: two_q.out_fraction,
+		})?,
+	};
+
+	Ok(classifier)
+}
+
+/// Creates a static placement classifier from `config`
+fn create_placement(config: &config::Config, placement_config: placement::Config) -> Result<Classifier, anyhow::Error> {
+	let tiered = self::create_tiered(config)?;
+	let placement = placement::Placement::new(placement_config, tiered).context("Unable to create placement")?;
+	Ok(Classifier::Placement(placement))
+}
+
+/// Creates an ARC classifier from `config`
+fn create_arc(config: &config::Config, arc_config: arc::Config) -> Result<Classifier, anyhow::Error> {
+	let tiered = self::create_tiered(config)?;
+	let arc = arc::Arc::new(arc_config, tiered).context("Unable to create arc")?;
+	Ok(Classifier::Arc(arc))
+}
+
+/// Creates the tiered memory for the classifiers other than hemem from `config`
+fn create_tiered(config: &config::Config) -> Result<tiered::TieredMemory, anyhow::Error> {
+	anyhow::ensure!(
+		!matches!(config.hemem.oom_behavior, config::OomBehavior::Swap) && config.hemem.swap.is_none(),
+		"Only the hemem classifier supports swap, other classifiers may set `oom_behavior` to `drop` instead"
+	);
+
+	let memories = self::create_memories(config)?;
+	let memories = hemem::Memories::new(memories, None).context("Unable to create memories")?;
+	tiered::TieredMemory::new(
+		memories,
+		self::epoch_length(config),
+		self::access_log(config),
+		self::oom_behavior(config),
+		self::bandwidth_window(config)?,
+		self::create_tlb(config)?,
+		self::create_thp(config)?,
+	)
+	.context("Unable to create tiered memory")
+}
+
+/// Creates the tlb from `config`, if any
+fn create_tlb(config: &config::Config) -> Result<Option<hemem::Tlb>, anyhow::Error> {
+	config
+		.hemem
+		.tlb
+		.as_ref()
+		.map(|tlb| {
+			let page_sizes = tlb
+				.entries
+				.iter()
+				.map(|entries| {
+					hemem::tlb::TlbEntries::new(entries.page_size_bytes, entries.entries, entries.associativity)
+						.with_context(|| {
+							format!("Unable to create tlb entries for page size {}", entries.page_size_bytes)
+						})
 				})
+				.collect::<Result<Vec<_>, _>>()?;
+			hemem::Tlb::new(
+				page_sizes,
+				tlb.page_size_bytes,
+				FemtoDuration::from_nanos_f64(tlb.page_walk_latency_ns),
+			)
+		})
+		.transpose()
+		.context("Unable to create tlb")
+}
+
+/// Creates the transparent huge pages from `config`, if any
+fn create_thp(config: &config::Config) -> Result<Option<hemem::Thp>, anyhow::Error> {
+	config
+		.hemem
+		.thp
+		.as_ref()
+		.map(|thp| {
+			hemem::Thp::new(hemem::thp::ThpConfig {
+				check_accesses:   thp.check_accesses,
+				split_skew:       thp.split_skew,
+				collapse_skew:    thp.collapse_skew,
+				split_latency:    FemtoDuration::from_nanos_f64(thp.split_latency_ns),
+				collapse_latency: FemtoDuration::from_nanos_f64(thp.collapse_latency_ns),
 			})
-			.collect(),
-	);
+		})
+		.transpose()
+		.context("Unable to create transparent huge pages")
+}
 
-	let sim_run_output = sim
-		.run(&mut pin_trace_reader, &mut hemem)
-		.context("Unable to run simulator")?;
+/// Returns the epoch length of `config`
+fn epoch_length(config: &config::Config) -> Option<hemem::statistics::EpochLength> {
+	config.hemem.epoch_length.map(|epoch_length| match epoch_length {
+		config::EpochLength::Time(time) => hemem::statistics::EpochLength::Time(time),
+		config::EpochLength::Records(records) => hemem::statistics::EpochLength::Records(records),
+	})
+}
 
-	if let Some(output_path) = &args.output_file {
-		let hemem_statistics = hemem.statistics();
-		let data = data::Data {
-			time_span: sim_run_output.time_span,
-			hemem:     data::HeMemData {
-				page_accesses:   data::PageAccesses {
-					accesses: hemem_statistics
-						.accesses()
-						.iter()
-						.map(|page_access| data::PageAccess {
-							page_ptr:       page_access.page_ptr.to_u64(),
-							time:           page_access.time,
-							mem_idx:        match page_access.mem {
-								hemem::statistics::AccessMem::Mapped(mem_idx) |
-								hemem::statistics::AccessMem::Resided(mem_idx) => mem_idx.to_usize(),
-							},
-							faulted:        matches!(page_access.mem, hemem::statistics::AccessMem::Mapped(_)),
-							kind:           match page_access.kind {
-								hemem::statistics::AccessKind::Read => data::PageAccessKind::Read,
-								hemem::statistics::AccessKind::Write => data::PageAccessKind::Write,
-							},
-							prev_temp:      page_access.prev_temperature,
-							cur_temp:       page_access.cur_temperature,
-							caused_cooling: page_access.caused_cooling,
+/// Returns the out-of-memory behavior of `config`
+fn oom_behavior(config: &config::Config) -> hemem::OomBehavior {
+	match config.hemem.oom_behavior {
+		config::OomBehavior::Error => hemem::OomBehavior::Error,
+		config::OomBehavior::Drop => hemem::OomBehavior::Drop,
+		config::OomBehavior::Swap => hemem::OomBehavior::Swap,
+	}
+}
+
+/// Returns if `config` keeps a log of all accesses
+fn access_log(config: &config::Config) -> bool {
+	config.hemem.access_log.unwrap_or(true)
+}
+
+/// Returns the bandwidth window of `config`
+fn bandwidth_window(config: &config::Config) -> Result<u64, anyhow::Error> {
+	let bandwidth_window = config
+		.hemem
+		.bandwidth_window_ns
+		.unwrap_or(config::HeMemConfig::DEFAULT_BANDWIDTH_WINDOW_NS);
+	anyhow::ensure!(bandwidth_window != 0, "Bandwidth window must not be 0");
+
+	Ok(bandwidth_window)
+}
+
+/// Creates all memories, excluding swap, from `config`
+fn create_memories(config: &config::Config) -> Result<Vec<hemem::Memory>, anyhow::Error> {
+	// Resolve the migration targets by memory name
+	let resolve_targets = |targets: &Option<Vec<config::HeMemTarget>>| {
+		targets
+			.as_ref()
+			.map(|targets| {
+				targets
+					.iter()
+					.map(|target| {
+						let mem_idx = config
+							.hemem
+							.memories
+							.iter()
+							.position(|mem| mem.name == target.name)
+							.with_context(|| format!("Unknown target memory {:?}", target.name))?;
+						Ok(hemem::memories::Target {
+							mem_idx,
+							distance: target.distance,
 						})
-						.collect(),
-				},
-				page_migrations: data::PageMigrations {
-					migrations: hemem_statistics
-						.page_migrations()
+					})
+					.collect::<Result<Vec<_>, anyhow::Error>>()
+			})
+			.transpose()
+	};
+
+	config
+		.hemem
+		.memories
+		.iter()
+		.map(|mem| {
+			let targets = hemem::memories::Targets {
+				promotion: resolve_targets(&mem.promotion_targets)
+					.with_context(|| format!("Invalid promotion targets for memory {:?}", mem.name))?,
+				demotion:  resolve_targets(&mem.demotion_targets)
+					.with_context(|| format!("Invalid demotion targets for memory {:?}", mem.name))?,
+			};
+			let latencies = hemem::memories::AccessLatencies {
+				read:  FemtoDuration::from_nanos_f64(mem.read_latency_ns),
+				write: FemtoDuration::from_nanos_f64(mem.write_latency_ns),
+				fault: FemtoDuration::from_nanos_f64(mem.fault_latency_ns),
+			};
+			let bandwidths = hemem::memories::Bandwidths {
+				read:  mem.read_bandwidth_gb_per_sec,
+				write: mem.write_bandwidth_gb_per_sec,
+			};
+			for bandwidth in [bandwidths.read, bandwidths.write].into_iter().flatten() {
+				anyhow::ensure!(bandwidth > 0.0, "Bandwidths of memory {:?} must be positive", mem.name);
+			}
+			Ok(hemem::Memory::new(
+				&mem.name,
+				mem.page_capacity,
+				latencies,
+				targets,
+				bandwidths,
+			))
+		})
+		.collect()
+}
+
+/// Creates the hemem classifier from `config`
+fn create_hemem(config: &config::Config) -> Result<hemem::HeMem, anyhow::Error> {
+	let oom_behavior = self::oom_behavior(config);
+	anyhow::ensure!(
+		!matches!(oom_behavior, hemem::OomBehavior::Swap) || config.hemem.swap.is_some(),
+		"A swap memory must be configured to swap when out of memory"
+	);
+
+	let memories = self::create_memories(config)?;
+	let swap = config.hemem.swap.as_ref().map(|swap| {
+		let latencies = hemem::memories::AccessLatencies {
+			read:  FemtoDuration::from_nanos_f64(swap.read_latency_ns),
+			write: FemtoDuration::from_nanos_f64(swap.write_latency_ns),
+			fault: FemtoDuration::from_nanos_f64(swap.fault_latency_ns),
+		};
+		hemem::Memory::new(
+			"swap",
+			usize::MAX,
+			latencies,
+			hemem::memories::Targets::default(),
+			hemem::memories::Bandwidths::default(),
+		)
+	});
+
+	let tlb = self::create_tlb(config)?;
+
+	let thp = self::create_thp(config)?;
+
+	hemem::HeMem::new(
+		hemem::Config {
+			read_hot_threshold: config.hemem.read_hot_threshold,
+			write_hot_threshold: config.hemem.write_hot_threshold,
+			global_cooling_threshold: config.hemem.global_cooling_threshold,
+			temperature_weights: hemem::TemperatureWeights {
+				read:  config.hemem.temperature.read_weight,
+				write: config.hemem.temperature.write_weight,
+			},
+			hotness: match config.hemem.hotness {
+				config::HeMemHotness::HalvingClock => hemem::Hotness::HalvingClock(hotness::HalvingClock::default()),
+				config::HeMemHotness::Ema { half_life } =>
+					hemem::Hotness::Ema(hotness::Ema::new(half_life).context("Unable to create ema")?),
+				config::HeMemHotness::SlidingWindow { window } => hemem::Hotness::SlidingWindow(
+					hotness::SlidingWindow::new(window).context("Unable to create sliding window")?,
+				),
+			},
+			cold_page_selection: match config.hemem.cold_page_selection {
+				config::HeMemColdPageSelection::Address => hemem::ColdPageSelection::Address,
+				config::HeMemColdPageSelection::Coldest => hemem::ColdPageSelection::Coldest,
+				config::HeMemColdPageSelection::Lru => hemem::ColdPageSelection::Lru,
+			},
+			demotion_batch: config
+				.hemem
+				.demotion_batch
+				.unwrap_or(config::HeMemConfig::DEFAULT_DEMOTION_BATCH),
+			epoch_length: self::epoch_length(config),
+			access_log: self::access_log(config),
+			oom_behavior,
+			bandwidth_window: self::bandwidth_window(config)?,
+		},
+		memories,
+		swap,
+		tlb,
+		thp,
+	)
+}
+
+/// Writes the output data of `hemem_statistics` to `output_path`, with the page temperatures defined by `temperature`
+fn write_output(
+	output_path: &Path,
+	sim_run_output: &sim::RunOutput,
+	hemem_statistics: &hemem::Statistics,
+	temperature: Option<data::Temperature>,
+) -> Result<(), anyhow::Error> {
+	let data = data::Data {
+		time_span: sim_run_output.time_span.clone(),
+		hemem:     data::HeMemData {
+			page_accesses: hemem_statistics.accesses().map(|accesses| data::PageAccesses {
+				accesses: accesses
+					.iter()
+					.map(|page_access| data::PageAccess {
+						page_ptr:       page_access.page_ptr.to_u64(),
+						time:           page_access.time,
+						mem_idx:        match page_access.mem {
+							hemem::statistics::AccessMem::Mapped(mem_idx) |
+							hemem::statistics::AccessMem::Resided(mem_idx) |
+							hemem::statistics::AccessMem::SwappedIn(mem_idx) => mem_idx.to_usize(),
+						},
+						faulted:        matches!(
+							page_access.mem,
+							hemem::statistics::AccessMem::Mapped(_) | hemem::statistics::AccessMem::SwappedIn(_)
+						),
+						kind:           match page_access.kind {
+							hemem::statistics::AccessKind::Read => data::PageAccessKind::Read,
+							hemem::statistics::AccessKind::Write => data::PageAccessKind::Write,
+						},
+						prev_temp:      page_access.prev_temperature,
+						cur_temp:       page_access.cur_temperature,
+						caused_cooling: page_access.caused_cooling,
+					})
+					.collect(),
+			}),
+			page_migrations: data::PageMigrations {
+				migrations: hemem_statistics
+					.page_migrations()
+					.iter()
+					.map(|(page_ptr, page_migrations)| {
+						let migrations = page_migrations
+							.iter()
+							.map(move |page_migration| data::PageMigration {
+								prev_mem_idx: page_migration.prev_mem_idx.map(MemIdx::to_usize),
+								cur_mem_idx:  page_migration.cur_mem_idx.to_usize(),
+								time:         page_migration.time,
+							})
+							.collect();
+
+						(page_ptr.to_u64(), migrations)
+					})
+					.collect(),
+			},
+			epochs: hemem_statistics
+				.epochs()
+				.map(|epoch| data::Epoch {
+					start_time:       epoch.start_time,
+					end_time:         epoch.end_time,
+					records:          epoch.records,
+					memories:         epoch
+						.memories
 						.iter()
-						.map(|(page_ptr, page_migrations)| {
-							let migrations = page_migrations
-								.iter()
-								.map(move |page_migration| data::PageMigration {
-									prev_mem_idx: page_migration.prev_mem_idx.map(MemIdx::to_usize),
-									cur_mem_idx:  page_migration.cur_mem_idx.to_usize(),
-									time:         page_migration.time,
-								})
-								.collect();
-
-							(page_ptr.to_u64(), migrations)
+						.map(|epoch_mem| data::EpochMemory {
+							reads:             epoch_mem.reads,
+							writes:            epoch_mem.writes,
+							faults:            epoch_mem.faults,
+							read_bytes:        epoch_mem.read_bytes,
+							write_bytes:       epoch_mem.write_bytes,
+							queueing_delay_ns: epoch_mem.queueing_delay.as_nanos_f64(),
+							occupancy:         epoch_mem.occupancy,
+							generation_sizes:  epoch_mem.generation_sizes.clone(),
 						})
 						.collect(),
-				},
-			},
-		};
+					fast_hit_rate:    epoch.fast_hit_rate(),
+					promotions:       epoch.promotions,
+					demotions:        epoch.demotions,
+					coolings:         epoch.coolings,
+					dropped_accesses: epoch.dropped_accesses,
+					major_faults:     epoch.major_faults,
+					tlb_hits:         epoch.tlb_hits,
+					tlb_misses:       epoch.tlb_misses,
+					thp_splits:       epoch.thp_splits,
+					thp_collapses:    epoch.thp_collapses,
+					thp_latency_ns:   epoch.thp_latency.as_nanos_f64(),
+					hot_threshold:    epoch.hot_threshold,
+					latency_ns:       epoch.latency.as_nanos_f64(),
+				})
+				.collect(),
+			page_writes: hemem_statistics
+				.page_write_distributions()
+				.into_iter()
+				.map(|page_writes| data::PageWrites {
+					pages: page_writes.pages,
+					total: page_writes.total,
+					max:   page_writes.max,
+					p50:   page_writes.p50,
+					p90:   page_writes.p90,
+					p99:   page_writes.p99,
+				})
+				.collect(),
+			thp_events: hemem_statistics
+				.thp_events()
+				.iter()
+				.map(|thp_event| data::ThpEvent {
+					time:       thp_event.time,
+					region_ptr: thp_event.region_ptr,
+					kind:       match thp_event.kind {
+						hemem::statistics::ThpEventKind::Split => data::ThpEventKind::Split,
+						hemem::statistics::ThpEventKind::Collapse => data::ThpEventKind::Collapse,
+					},
+					cost_ns:    thp_event.cost.as_nanos_f64(),
+				})
+				.collect(),
+			hot_thresholds: hemem_statistics
+				.hot_thresholds()
+				.iter()
+				.map(|hot_threshold| data::HotThreshold {
+					time:      hot_threshold.time,
+					threshold: hot_threshold.threshold,
+				})
+				.collect(),
+			temperature,
+		},
+	};
 
-		let output_file = fs::File::create(output_path).context("Unable to create output file")?;
-		let mut output_file = ParCompress::<gzp::deflate::Mgzip>::builder().from_writer(output_file);
-		bincode::encode_into_std_write(data, &mut output_file, bincode::config::standard())
-			.context("Unable to write to output file")?;
-	}
+	let output_file = fs::File::create(output_path).context("Unable to create output file")?;
+	let mut output_file = ParCompress::<gzp::deflate::Mgzip>::builder().from_writer(output_file);
+	bincode::encode_into_std_write(data, &mut output_file, bincode::config::standard())
+		.context("Unable to write to output file")?;
 
 	Ok(())
 }
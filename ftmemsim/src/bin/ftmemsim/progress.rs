@@ -0,0 +1,38 @@
+//! Progress
+
+// Imports
+use {anyhow::Context, ftmemsim::sim, std::io};
+
+/// Progress observer that writes each progress as a json line
+#[derive(Debug)]
+pub struct JsonLinesProgress<W> {
+	/// Writer
+	writer: W,
+}
+
+impl<W> JsonLinesProgress<W> {
+	/// Creates a new json lines progress observer
+	pub fn new(writer: W) -> Self {
+		Self { writer }
+	}
+}
+
+impl<W: io::Write> JsonLinesProgress<W> {
+	/// Writes a progress as a json line
+	fn write(&mut self, progress: &sim::Progress) -> Result<(), anyhow::Error> {
+		serde_json::to_writer(&mut self.writer, progress).context("Unable to serialize progress")?;
+		self.writer.write_all(b"\n").context("Unable to write newline")?;
+		self.writer.flush().context("Unable to flush")?;
+
+		Ok(())
+	}
+}
+
+impl<W: io::Write> sim::ProgressObserver for JsonLinesProgress<W> {
+	fn on_progress(&mut self, progress: &sim::Progress) {
+		// Note: We don't want to stop the simulation just because we couldn't report progress
+		if let Err(err) = self.write(progress) {
+			tracing::warn!("Unable to write progress: {err:?}");
+		}
+	}
+}
@@ -0,0 +1,392 @@
+//! Run summary
+
+// Imports
+use {
+	ftmemsim::{cache, classifiers::hemem, config, sim},
+	ftmemsim_util::FemtoDuration,
+	std::{fmt, path::PathBuf},
+};
+
+/// Seconds per year
+const SECS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+/// Run summary
+#[derive(Debug)]
+#[derive(serde::Serialize)]
+pub struct Summary {
+	/// Config file
+	pub config_file: PathBuf,
+
+	/// Total accesses that reached the memories
+	pub accesses: usize,
+
+	/// Reads
+	pub reads: usize,
+
+	/// Writes
+	pub writes: usize,
+
+	/// Caches
+	pub caches: Vec<CacheSummary>,
+
+	/// Memories
+	pub memories: Vec<MemorySummary>,
+
+	/// Hit ratio of the fastest memory
+	pub fast_hit_ratio: f64,
+
+	/// Hit ratio of the TLB, if any
+	pub tlb_hit_ratio: Option<f64>,
+
+	/// TLB misses
+	pub tlb_misses: usize,
+
+	/// Transparent huge page splits
+	pub thp_splits: usize,
+
+	/// Transparent huge page collapses
+	pub thp_collapses: usize,
+
+	/// Latency of all transparent huge page splits and collapses (in nanoseconds)
+	pub thp_latency_ns: f64,
+
+	/// Promotions (migrations to a faster memory)
+	pub promotions: usize,
+
+	/// Demotions (migrations to a slower memory)
+	pub demotions: usize,
+
+	/// Global coolings
+	pub global_coolings: usize,
+
+	/// Accesses dropped due to all memories being full
+	pub dropped_accesses: usize,
+
+	/// Major faults (accesses that swapped in a page)
+	pub major_faults: usize,
+
+	/// Unique pages accessed
+	pub unique_pages: usize,
+
+	/// Energy (in joules)
+	pub energy_j: f64,
+
+	/// Cost of all memories (in dollars)
+	pub cost_usd: f64,
+
+	/// Simulated time (in nanoseconds)
+	pub simulated_time_ns: f64,
+
+	/// Wall-clock time (in seconds)
+	pub wall_clock_secs: f64,
+
+	/// Records simulated per wall-clock second
+	pub records_per_sec: f64,
+}
+
+impl Summary {
+	/// Creates the summary of a run, where it's classifier handled `traces` traces
+	pub fn new(
+		config_file: PathBuf,
+		config: &config::Config,
+		sim_run_output: &sim::RunOutput,
+		traces: usize,
+		caches: &[cache::Cache],
+		statistics: &hemem::Statistics,
+	) -> Self {
+		let total = statistics.total();
+		let simulated_time_secs = total.latency.as_nanos_f64() / FemtoDuration::NANOS_PER_SEC as f64;
+		let page_write_distributions = statistics.page_write_distributions();
+
+		// Note: The wear lifetime is projected from the trace's time, not the simulated time,
+		//       since that's the rate the application writes at.
+		let trace_secs = sim_run_output
+			.time_span
+			.as_ref()
+			.map(|time_span| (time_span.end - time_span.start) as f64 / FemtoDuration::NANOS_PER_SEC as f64);
+
+		// Note: The swap memory has no energy or cost
+		let mem_configs = config
+			.hemem
+			.memories
+			.iter()
+			.map(|mem_config| (mem_config.name.as_str(), Some(mem_config)))
+			.chain(config.hemem.swap.as_ref().map(|_| ("swap", None)));
+		let memories = mem_configs
+			.enumerate()
+			.map(|(mem_idx, (mem_name, mem_config))| {
+				// Note: Memories that were never accessed might not be in the totals
+				let mem = total.memories.get(mem_idx).cloned().unwrap_or_default();
+				let page_writes = page_write_distributions.get(mem_idx).copied().unwrap_or_default();
+
+				// Note: Migrations are counted as reading / writing each cache line of the page
+				let (energy_j, cost_usd) = match mem_config {
+					Some(mem_config) => {
+						let capacity_gb = (mem_config.page_capacity as u64 * hemem::memories::PAGE_BYTES) as f64 / 1e9;
+						let dynamic_energy_nj = (mem.read_bytes / hemem::memories::ACCESS_BYTES) as f64 *
+							mem_config.read_energy_nj +
+							(mem.write_bytes / hemem::memories::ACCESS_BYTES) as f64 * mem_config.write_energy_nj;
+						let static_energy_j = mem_config.static_power_w_per_gb * capacity_gb * simulated_time_secs;
+
+						(
+							dynamic_energy_nj / 1e9 + static_energy_j,
+							mem_config.cost_per_gb * capacity_gb,
+						)
+					},
+					None => (0.0, 0.0),
+				};
+
+				// Note: Without wear-leveling, the memory wears out once it's most written page does,
+				//       while with perfect wear-leveling, the writes are spread over all of it's pages.
+				let (lifetime_secs, wear_leveled_lifetime_secs) = match (
+					mem_config.and_then(|mem_config| mem_config.endurance_writes),
+					trace_secs,
+				) {
+					(Some(endurance_writes), Some(trace_secs)) if page_writes.total != 0 => {
+						let capacity = mem_config.map_or(0, |mem_config| mem_config.page_capacity);
+						(
+							Some(endurance_writes as f64 / page_writes.max as f64 * trace_secs),
+							Some(endurance_writes as f64 * capacity as f64 / page_writes.total as f64 * trace_secs),
+						)
+					},
+					_ => (None, None),
+				};
+
+				MemorySummary {
+					name: mem_name.to_owned(),
+					reads: mem.reads,
+					writes: mem.writes,
+					faults: mem.faults,
+					read_bytes: mem.read_bytes,
+					write_bytes: mem.write_bytes,
+					queueing_delay_ns: mem.queueing_delay.as_nanos_f64(),
+					energy_j,
+					cost_usd,
+					page_writes_max: page_writes.max,
+					page_writes_p50: page_writes.p50,
+					page_writes_p90: page_writes.p90,
+					page_writes_p99: page_writes.p99,
+					lifetime_secs,
+					wear_leveled_lifetime_secs,
+				}
+			})
+			.collect::<Vec<_>>();
+
+		let caches = caches
+			.iter()
+			.map(|cache| {
+				let statistics = cache.statistics();
+				CacheSummary {
+					name:       cache.name().to_owned(),
+					hits:       statistics.hits,
+					misses:     statistics.misses,
+					hit_ratio:  statistics.hit_ratio(),
+					writebacks: statistics.writebacks,
+				}
+			})
+			.collect();
+
+		let energy_j = memories.iter().map(|mem| mem.energy_j).sum();
+		let cost_usd = memories.iter().map(|mem| mem.cost_usd).sum();
+		let wall_clock_secs = sim_run_output.duration.as_secs_f64();
+		Self {
+			config_file,
+			accesses: total.records,
+			reads: memories.iter().map(|mem| mem.reads).sum(),
+			writes: memories.iter().map(|mem| mem.writes).sum(),
+			caches,
+			memories,
+			fast_hit_ratio: total.fast_hit_rate(),
+			tlb_hit_ratio: config.hemem.tlb.as_ref().map(|_| total.tlb_hit_rate()),
+			tlb_misses: total.tlb_misses,
+			thp_splits: total.thp_splits,
+			thp_collapses: total.thp_collapses,
+			thp_latency_ns: total.thp_latency.as_nanos_f64(),
+			promotions: total.promotions,
+			demotions: total.demotions,
+			global_coolings: total.coolings,
+			dropped_accesses: total.dropped_accesses,
+			major_faults: total.major_faults,
+			unique_pages: statistics.unique_pages(),
+			energy_j,
+			cost_usd,
+			simulated_time_ns: total.latency.as_nanos_f64(),
+			wall_clock_secs,
+			records_per_sec: traces as f64 / wall_clock_secs,
+		}
+	}
+}
+
+impl fmt::Display for Summary {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "Summary for {:?}:", self.config_file)?;
+		writeln!(
+			f,
+			"\tAccesses: {} ({} reads, {} writes)",
+			self.accesses, self.reads, self.writes
+		)?;
+		for cache in &self.caches {
+			writeln!(
+				f,
+				"\tCache {}: {} hits, {} misses ({:.2}% hit ratio), {} writebacks",
+				cache.name,
+				cache.hits,
+				cache.misses,
+				100.0 * cache.hit_ratio,
+				cache.writebacks
+			)?;
+		}
+		for mem in &self.memories {
+			writeln!(
+				f,
+				"\tMemory {}: {} reads, {} writes, {} faults, {:.2} MiB read, {:.2} MiB written, {} queueing delay",
+				mem.name,
+				mem.reads,
+				mem.writes,
+				mem.faults,
+				mem.read_bytes as f64 / (1024.0 * 1024.0),
+				mem.write_bytes as f64 / (1024.0 * 1024.0),
+				FemtoDuration::from_nanos_f64(mem.queueing_delay_ns)
+			)?;
+			if mem.page_writes_max != 0 {
+				writeln!(
+					f,
+					"\t\tWrites per page: {} max, {} p99, {} p90, {} p50",
+					mem.page_writes_max, mem.page_writes_p99, mem.page_writes_p90, mem.page_writes_p50
+				)?;
+			}
+			if let (Some(lifetime_secs), Some(wear_leveled_lifetime_secs)) =
+				(mem.lifetime_secs, mem.wear_leveled_lifetime_secs)
+			{
+				writeln!(
+					f,
+					"\t\tProjected lifetime: {} ({} with wear-leveling)",
+					self::display_lifetime(lifetime_secs),
+					self::display_lifetime(wear_leveled_lifetime_secs)
+				)?;
+			}
+		}
+		writeln!(f, "\tFast memory hit ratio: {:.2}%", 100.0 * self.fast_hit_ratio)?;
+		if let Some(tlb_hit_ratio) = self.tlb_hit_ratio {
+			writeln!(
+				f,
+				"\tTLB hit ratio: {:.2}% ({} misses)",
+				100.0 * tlb_hit_ratio,
+				self.tlb_misses
+			)?;
+		}
+		writeln!(f, "\tPromotions: {}", self.promotions)?;
+		writeln!(f, "\tDemotions: {}", self.demotions)?;
+		writeln!(f, "\tGlobal coolings: {}", self.global_coolings)?;
+		if self.thp_splits != 0 || self.thp_collapses != 0 {
+			writeln!(
+				f,
+				"\tTHP: {} splits, {} collapses ({})",
+				self.thp_splits,
+				self.thp_collapses,
+				FemtoDuration::from_nanos_f64(self.thp_latency_ns)
+			)?;
+		}
+		if self.dropped_accesses != 0 {
+			writeln!(f, "\tDropped accesses: {}", self.dropped_accesses)?;
+		}
+		if self.major_faults != 0 {
+			writeln!(f, "\tMajor faults: {}", self.major_faults)?;
+		}
+		writeln!(f, "\tUnique pages: {}", self.unique_pages)?;
+		writeln!(f, "\tEnergy: {:.6}J", self.energy_j)?;
+		writeln!(f, "\tCost: ${:.2}", self.cost_usd)?;
+		writeln!(
+			f,
+			"\tSimulated time: {}",
+			FemtoDuration::from_nanos_f64(self.simulated_time_ns)
+		)?;
+		write!(
+			f,
+			"\tWall-clock time: {:.2}s ({:.0} records/s)",
+			self.wall_clock_secs, self.records_per_sec
+		)
+	}
+}
+
+/// Cache summary
+#[derive(Debug)]
+#[derive(serde::Serialize)]
+pub struct CacheSummary {
+	/// Name
+	pub name: String,
+
+	/// Hits
+	pub hits: usize,
+
+	/// Misses
+	pub misses: usize,
+
+	/// Hit ratio
+	pub hit_ratio: f64,
+
+	/// Dirty lines written back to the next level
+	pub writebacks: usize,
+}
+
+/// Memory summary
+#[derive(Debug)]
+#[derive(serde::Serialize)]
+pub struct MemorySummary {
+	/// Name
+	pub name: String,
+
+	/// Reads
+	pub reads: usize,
+
+	/// Writes
+	pub writes: usize,
+
+	/// Faults
+	pub faults: usize,
+
+	/// Bytes read, including migrations
+	pub read_bytes: u64,
+
+	/// Bytes written, including migrations
+	pub write_bytes: u64,
+
+	/// Queueing delay due to the memory's bandwidth (in nanoseconds)
+	pub queueing_delay_ns: f64,
+
+	/// Energy (in joules)
+	pub energy_j: f64,
+
+	/// Cost (in dollars)
+	pub cost_usd: f64,
+
+	/// Max writes to a single page, including migrations
+	pub page_writes_max: u64,
+
+	/// Median writes per page
+	pub page_writes_p50: u64,
+
+	/// 90th percentile of writes per page
+	pub page_writes_p90: u64,
+
+	/// 99th percentile of writes per page
+	pub page_writes_p99: u64,
+
+	/// Projected lifetime until the most written page wears out (in seconds)
+	pub lifetime_secs: Option<f64>,
+
+	/// Projected lifetime with perfect wear-leveling (in seconds)
+	pub wear_leveled_lifetime_secs: Option<f64>,
+}
+
+/// Displays a lifetime in years, or as a duration if it's shorter than a year
+fn display_lifetime(lifetime_secs: f64) -> impl fmt::Display {
+	ftmemsim_util::DisplayWrapper::new(move |f| match lifetime_secs >= SECS_PER_YEAR {
+		true => write!(f, "{:.2} years", lifetime_secs / SECS_PER_YEAR),
+		false => write!(
+			f,
+			"{}",
+			FemtoDuration::from_nanos_f64(lifetime_secs * FemtoDuration::NANOS_PER_SEC as f64)
+		),
+	})
+}
@@ -0,0 +1,321 @@
+//! Parameter sweeps
+
+// Imports
+use {
+	anyhow::Context,
+	itertools::Itertools,
+	serde_json::Value,
+	std::{collections::BTreeMap, path::PathBuf, str::FromStr},
+};
+
+/// Sweep axis
+#[derive(Clone, Debug)]
+pub struct SweepAxis {
+	/// Path into the config
+	pub path: String,
+
+	/// All values
+	pub values: Vec<Value>,
+}
+
+impl FromStr for SweepAxis {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (path, values) = s.split_once('=').context("Expected `<path>=<values>`")?;
+		let path = path.trim();
+		let values = values.trim();
+
+		// Note: We parse the path upfront to report errors before running anything
+		let _ = self::parse_path(path).with_context(|| format!("Unable to parse path {path:?}"))?;
+
+		let values = match values.starts_with('[') {
+			true => serde_json::from_str::<Vec<Value>>(values).context("Unable to parse list of values")?,
+			false => self::parse_range(values).with_context(|| format!("Unable to parse range {values:?}"))?,
+		};
+		anyhow::ensure!(!values.is_empty(), "Axis {path:?} had no values");
+
+		Ok(Self {
+			path: path.to_owned(),
+			values,
+		})
+	}
+}
+
+/// Returns all combinations of values of `axes`, in the same order as `axes`
+pub fn combinations(axes: &[SweepAxis]) -> Vec<Vec<Value>> {
+	// Note: `multi_cartesian_product` yields nothing without any axes, but
+	//       we still want to run the base config as-is.
+	match axes.is_empty() {
+		true => vec![vec![]],
+		false => axes
+			.iter()
+			.map(|axis| axis.values.iter().cloned())
+			.multi_cartesian_product()
+			.collect(),
+	}
+}
+
+/// Sets the value at `path` of `config` to `value`.
+///
+/// # Errors
+/// Returns an error if `path` doesn't exist within `config`.
+pub fn set_param(config: &mut Value, path: &str, value: Value) -> Result<(), anyhow::Error> {
+	let mut cur = config;
+	for segment in self::parse_path(path)? {
+		cur = match segment {
+			PathSegment::Key(key) => cur
+				.as_object_mut()
+				.with_context(|| format!("Expected an object to access {key:?}"))?
+				.get_mut(key)
+				.with_context(|| format!("Config has no key {key:?}"))?,
+			PathSegment::Idx(idx) => cur
+				.as_array_mut()
+				.with_context(|| format!("Expected an array to access index {idx}"))?
+				.get_mut(idx)
+				.with_context(|| format!("Config has no index {idx}"))?,
+		};
+	}
+
+	*cur = value;
+	Ok(())
+}
+
+/// Sweep manifest
+#[derive(Debug)]
+#[derive(serde::Serialize)]
+pub struct Manifest {
+	/// Trace file
+	pub trace_file: PathBuf,
+
+	/// Base config file
+	pub base_config_file: PathBuf,
+
+	/// All runs
+	pub runs: Vec<ManifestRun>,
+}
+
+/// Sweep manifest run
+#[derive(Debug)]
+#[derive(serde::Serialize)]
+pub struct ManifestRun {
+	/// Config file, relative to the manifest
+	pub config_file: PathBuf,
+
+	/// Output file, relative to the manifest
+	pub output_file: PathBuf,
+
+	/// Parameter values, by path
+	pub params: BTreeMap<String, Value>,
+}
+
+/// Path segment
+#[derive(Clone, Copy, Debug)]
+enum PathSegment<'a> {
+	/// Object key
+	Key(&'a str),
+
+	/// Array index
+	Idx(usize),
+}
+
+/// Parses a path, such as `hemem.memories[0].page_capacity`
+fn parse_path(path: &str) -> Result<Vec<PathSegment<'_>>, anyhow::Error> {
+	let mut segments = vec![];
+	for part in path.split('.') {
+		// Split off any indexes, such as `memories[0][1]`
+		let (key, mut indexes) = match part.split_once('[') {
+			Some((key, rest)) => (key, Some(rest)),
+			None => (part, None),
+		};
+		anyhow::ensure!(!key.is_empty(), "Path had an empty key");
+		segments.push(PathSegment::Key(key));
+
+		while let Some(rest) = indexes {
+			let (idx, rest) = rest.split_once(']').context("Expected `]` after index")?;
+			let idx = idx
+				.trim()
+				.parse()
+				.with_context(|| format!("Unable to parse index {idx:?}"))?;
+			segments.push(PathSegment::Idx(idx));
+
+			indexes = match rest {
+				"" => None,
+				_ => Some(rest.strip_prefix('[').context("Expected `[` after index")?),
+			};
+		}
+	}
+
+	Ok(segments)
+}
+
+/// Parses a range, such as `200..4500 step 250` or `0.5..=2.0 step 0.5`.
+///
+/// If the start, end and step are all integers, the values will also be integers.
+fn parse_range(range: &str) -> Result<Vec<Value>, anyhow::Error> {
+	let (range, step) = match range.split_once("step") {
+		Some((range, step)) => (range.trim(), Some(step.trim())),
+		None => (range, None),
+	};
+
+	let (start, end, inclusive) = match range.split_once("..") {
+		Some((start, end)) => match end.strip_prefix('=') {
+			Some(end) => (start, end, true),
+			None => (start, end, false),
+		},
+		None => anyhow::bail!("Expected `<start>..<end>`"),
+	};
+
+	let parse_num = |s: &str| serde_json::from_str::<serde_json::Number>(s.trim());
+	let start = parse_num(start).context("Unable to parse start")?;
+	let end = parse_num(end).context("Unable to parse end")?;
+	let step = match step {
+		Some(step) => parse_num(step).context("Unable to parse step")?,
+		None => serde_json::Number::from(1),
+	};
+
+	// If all are integers, keep them as integers
+	if let (Some(start), Some(end), Some(step)) = (start.as_i64(), end.as_i64(), step.as_i64()) {
+		anyhow::ensure!(step > 0, "Step must be positive");
+		let values = match inclusive {
+			true => (start..=end).step_by(step as usize).map(Value::from).collect(),
+			false => (start..end).step_by(step as usize).map(Value::from).collect(),
+		};
+		return Ok(values);
+	}
+
+	// Else use floats
+	let start = start.as_f64().context("Start wasn't a number")?;
+	let end = end.as_f64().context("End wasn't a number")?;
+	let step = step.as_f64().context("Step wasn't a number")?;
+	anyhow::ensure!(step > 0.0, "Step must be positive");
+
+	// Note: We calculate each value from the start to avoid accumulating errors
+	let values = (0..)
+		.map(|idx| start + step * idx as f64)
+		.take_while(|&value| match inclusive {
+			true => value <= end,
+			false => value < end,
+		})
+		.map(Value::from)
+		.collect();
+	Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+	// Imports
+	use {super::*, serde_json::json};
+
+	/// Parses `s` as an axis, returning it's values
+	fn axis_values(s: &str) -> Result<Vec<Value>, anyhow::Error> {
+		s.parse::<SweepAxis>().map(|axis| axis.values)
+	}
+
+	#[test]
+	fn parse_int_range() {
+		assert_eq!(axis_values("a=200..1000 step 250").unwrap(), [
+			json!(200),
+			json!(450),
+			json!(700),
+			json!(950)
+		]);
+		assert_eq!(axis_values("a=1..4").unwrap(), [json!(1), json!(2), json!(3)]);
+	}
+
+	#[test]
+	fn parse_inclusive_range() {
+		assert_eq!(axis_values("a=0..=4 step 2").unwrap(), [json!(0), json!(2), json!(4)]);
+		assert_eq!(axis_values("a=0..4 step 2").unwrap(), [json!(0), json!(2)]);
+	}
+
+	#[test]
+	fn parse_float_range() {
+		assert_eq!(axis_values("a=0.5..=2.0 step 0.5").unwrap(), [
+			json!(0.5),
+			json!(1.0),
+			json!(1.5),
+			json!(2.0)
+		]);
+		assert_eq!(axis_values("a=0..1 step 0.5").unwrap(), [json!(0.0), json!(0.5)]);
+	}
+
+	#[test]
+	fn parse_list() {
+		let axis = " hemem.read_hot_threshold = [4, 8, 16] ".parse::<SweepAxis>().unwrap();
+		assert_eq!(axis.path, "hemem.read_hot_threshold");
+		assert_eq!(axis.values, [json!(4), json!(8), json!(16)]);
+		assert_eq!(axis_values(r#"a=["x", {"y": 1}]"#).unwrap(), [
+			json!("x"),
+			json!({"y": 1})
+		]);
+	}
+
+	#[test]
+	fn parse_path() {
+		let axis = "hemem.memories[0].page_capacity=[1]".parse::<SweepAxis>().unwrap();
+		assert_eq!(axis.path, "hemem.memories[0].page_capacity");
+		assert!("a[0][1]=[1]".parse::<SweepAxis>().is_ok());
+	}
+
+	#[test]
+	fn parse_malformed() {
+		for s in [
+			"",
+			"a",
+			"a=",
+			"=[1]",
+			"a..b=[1]",
+			"a[x]=[1]",
+			"a[0=[1]",
+			"a[0]b=[1]",
+			"a=[]",
+			"a=[1,",
+			"a=1-5",
+			"a=1..x",
+			"a=5..5",
+			"a=5..1",
+			"a=1..5 step 0",
+			"a=1..5 step -1",
+			"a=0.5..2.0 step 0.0",
+			"a=1..5 step x",
+		] {
+			assert!(s.parse::<SweepAxis>().is_err(), "Parsed malformed axis {s:?}");
+		}
+	}
+
+	#[test]
+	fn combinations_without_axes() {
+		assert_eq!(combinations(&[]), [Vec::<Value>::new()]);
+	}
+
+	#[test]
+	fn combinations_of_axes() {
+		let axes = ["a=[1, 2]", "b=[3, 4, 5]"]
+			.map(|s| s.parse::<SweepAxis>().unwrap())
+			.to_vec();
+		assert_eq!(combinations(&axes), [
+			[json!(1), json!(3)],
+			[json!(1), json!(4)],
+			[json!(1), json!(5)],
+			[json!(2), json!(3)],
+			[json!(2), json!(4)],
+			[json!(2), json!(5)],
+		]);
+	}
+
+	#[test]
+	fn set_params() {
+		let mut config = json!({ "hemem": { "memories": [{ "page_capacity": 1 }], "read_hot_threshold": 2 } });
+		set_param(&mut config, "hemem.memories[0].page_capacity", json!(3)).unwrap();
+		set_param(&mut config, "hemem.read_hot_threshold", json!(4)).unwrap();
+		assert_eq!(
+			config,
+			json!({ "hemem": { "memories": [{ "page_capacity": 3 }], "read_hot_threshold": 4 } })
+		);
+
+		assert!(set_param(&mut config, "hemem.missing", json!(0)).is_err());
+		assert!(set_param(&mut config, "hemem.memories[1].page_capacity", json!(0)).is_err());
+		assert!(set_param(&mut config, "hemem[0]", json!(0)).is_err());
+	}
+}
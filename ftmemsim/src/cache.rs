@@ -0,0 +1,327 @@
+//! CPU caches
+//!
+//! Models a hierarchy of set-associative, write-back, write-allocate caches
+//! in front of a classifier, so that it only receives the misses and writebacks
+//! of the last level cache.
+//!
+//! Each cache keeps lines of it's own size, while the classifier always receives
+//! whole pages, so each miss or writeback of the last level is forwarded as an
+//! access to the page containing it's line.
+//!
+//! Note: The trace records only have page granularity (their addresses are
+//!       `4KiB`-aligned), so by default each line holds a whole page. Smaller lines
+//!       only ever use the first line of each page of the trace.
+
+// Imports
+use {
+	crate::{
+		classifiers::{hemem::memories, observer::Observer},
+		pin_trace,
+		sim,
+	},
+	std::fmt,
+};
+
+/// Default line size of caches (in bytes)
+pub const DEFAULT_LINE_SIZE: u64 = memories::PAGE_BYTES;
+
+/// Cache filter.
+///
+/// Wraps a classifier, forwarding only the traces that miss or are written back from all caches.
+#[derive(Debug)]
+pub struct CacheFilter<C> {
+	/// Caches, from the first to the last level
+	caches: Vec<Cache>,
+
+	/// Inner classifier
+	inner: C,
+}
+
+impl<C> CacheFilter<C> {
+	/// Creates a new cache filter.
+	///
+	/// If `caches` is empty, all traces are forwarded.
+	pub fn new(caches: Vec<Cache>, inner: C) -> Self {
+		Self { caches, inner }
+	}
+
+	/// Returns the caches
+	pub fn caches(&self) -> &[Cache] {
+		&self.caches
+	}
+
+	/// Returns the inner classifier
+	pub fn inner(&self) -> &C {
+		&self.inner
+	}
+}
+
+impl<C: sim::Classifier> CacheFilter<C> {
+	/// Accesses line `addr` in cache level `level_idx`, forwarding to the next level on misses and writebacks
+	fn access(&mut self, level_idx: usize, time: u64, addr: u64, kind: AccessKind) -> Result<(), anyhow::Error> {
+		// If we're past the last level, forward the line's page to the classifier
+		let Some(cache) = self.caches.get_mut(level_idx) else {
+			let addr = addr & !(memories::PAGE_BYTES - 1);
+			let kind = match kind {
+				AccessKind::Read => pin_trace::RecordAccessKind::Read,
+				AccessKind::Write | AccessKind::Writeback => pin_trace::RecordAccessKind::Write,
+			};
+			return self.inner.handle_trace(sim::Trace {
+				record: pin_trace::Record { time, addr, kind },
+			});
+		};
+
+		let output = cache.access(addr, kind);
+		if let Some(writeback_addr) = output.writeback_addr {
+			self.access(level_idx + 1, time, writeback_addr, AccessKind::Writeback)?;
+		}
+		if let Some(fill_addr) = output.fill_addr {
+			self.access(level_idx + 1, time, fill_addr, AccessKind::Read)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl<C: sim::Classifier> sim::Classifier for CacheFilter<C> {
+	fn register_trace(&mut self, trace: sim::Trace) {
+		self.inner.register_trace(trace);
+	}
+
+	fn handle_trace(&mut self, trace: sim::Trace) -> Result<(), anyhow::Error> {
+		let kind = match trace.record.kind {
+			pin_trace::RecordAccessKind::Read => AccessKind::Read,
+			pin_trace::RecordAccessKind::Write => AccessKind::Write,
+		};
+
+		self.access(0, trace.record.time, trace.record.addr, kind)
+	}
+
+	fn finish(&mut self) {
+		self.inner.finish();
+	}
+
+	fn add_observer(&mut self, observer: impl Observer + 'static) {
+		self.inner.add_observer(observer);
+	}
+
+	fn fmt_debug(&mut self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+		self.inner.fmt_debug(f)?;
+
+		for cache in &self.caches {
+			let statistics = cache.statistics();
+			writeln!(
+				f,
+				"Cache {}: {} hits, {} misses ({:.2}% hit ratio), {} writebacks",
+				cache.name(),
+				statistics.hits,
+				statistics.misses,
+				100.0 * statistics.hit_ratio(),
+				statistics.writebacks
+			)?;
+		}
+
+		Ok(())
+	}
+
+	fn debug_stats(&mut self) -> serde_json::Value {
+		let mut stats = self.inner.debug_stats();
+
+		// Note: We only add the caches if the inner classifier gave us an object to add them to
+		if let (Some(stats), false) = (stats.as_object_mut(), self.caches.is_empty()) {
+			let caches = self
+				.caches
+				.iter()
+				.map(|cache| {
+					let statistics = cache.statistics();
+					serde_json::json!({
+						"name": cache.name(),
+						"hits": statistics.hits,
+						"misses": statistics.misses,
+						"writebacks": statistics.writebacks,
+					})
+				})
+				.collect::<Vec<_>>();
+			stats.insert("caches".to_owned(), serde_json::Value::Array(caches));
+		}
+
+		stats
+	}
+}
+
+/// Set-associative, write-back, write-allocate cache with LRU replacement
+pub struct Cache {
+	/// Name
+	name: String,
+
+	/// Line size (in bytes)
+	line_size: u64,
+
+	/// Associativity
+	associativity: usize,
+
+	/// Sets.
+	///
+	/// Each set is ordered from the most to the least recently used line.
+	sets: Vec<Vec<Line>>,
+
+	/// Statistics
+	statistics: CacheStatistics,
+}
+
+impl Cache {
+	/// Creates a new cache.
+	///
+	/// # Errors
+	/// Returns an error if the line size isn't a power of two no larger than a page,
+	/// or if the size isn't a multiple of the line size times the associativity.
+	pub fn new(name: String, size: u64, associativity: usize, line_size: u64) -> Result<Self, anyhow::Error> {
+		anyhow::ensure!(
+			line_size.is_power_of_two() && line_size <= memories::PAGE_BYTES,
+			"Cache line size must be a power of two no larger than a page ({}), found {line_size}",
+			memories::PAGE_BYTES
+		);
+		anyhow::ensure!(associativity != 0, "Cache associativity must not be 0");
+		let set_size = line_size * associativity as u64;
+		anyhow::ensure!(
+			size != 0 && size.is_multiple_of(set_size),
+			"Cache size must be a non-zero multiple of the line size times the associativity ({set_size})"
+		);
+		let sets_len = usize::try_from(size / set_size).expect("Number of sets didn't fit into a `usize`");
+
+		Ok(Self {
+			name,
+			line_size,
+			associativity,
+			sets: vec![Vec::with_capacity(associativity); sets_len],
+			statistics: CacheStatistics::default(),
+		})
+	}
+
+	/// Returns the name of this cache
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Returns the line size of this cache (in bytes)
+	pub fn line_size(&self) -> u64 {
+		self.line_size
+	}
+
+	/// Returns the statistics of this cache
+	pub fn statistics(&self) -> &CacheStatistics {
+		&self.statistics
+	}
+
+	/// Accesses `addr` in this cache
+	fn access(&mut self, addr: u64, kind: AccessKind) -> AccessOutput {
+		let line_addr = addr & !(self.line_size - 1);
+		let set_idx = ((line_addr / self.line_size) % self.sets.len() as u64) as usize;
+		let set = &mut self.sets[set_idx];
+		let is_write = matches!(kind, AccessKind::Write | AccessKind::Writeback);
+
+		// If we hit, move the line to the front
+		if let Some(line_idx) = set.iter().position(|line| line.addr == line_addr) {
+			set[..=line_idx].rotate_right(1);
+			set[0].dirty |= is_write;
+			self.statistics.hits += 1;
+			return AccessOutput {
+				fill_addr:      None,
+				writeback_addr: None,
+			};
+		}
+
+		// Else evict the least recently used line, if the set is full, and insert the new line
+		self.statistics.misses += 1;
+		let evicted_line = match set.len() >= self.associativity {
+			true => set.pop(),
+			false => None,
+		};
+		set.insert(0, Line {
+			addr:  line_addr,
+			dirty: is_write,
+		});
+
+		let writeback_addr = evicted_line.filter(|line| line.dirty).map(|line| line.addr);
+		if writeback_addr.is_some() {
+			self.statistics.writebacks += 1;
+		}
+
+		// Note: Writebacks overwrite the whole line, so they don't need to fetch it
+		AccessOutput {
+			fill_addr: match kind {
+				AccessKind::Read | AccessKind::Write => Some(line_addr),
+				AccessKind::Writeback => None,
+			},
+			writeback_addr,
+		}
+	}
+}
+
+impl fmt::Debug for Cache {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Cache")
+			.field("name", &self.name)
+			.field("line_size", &self.line_size)
+			.field("associativity", &self.associativity)
+			.field("sets", &self.sets.len())
+			.field("statistics", &self.statistics)
+			.finish()
+	}
+}
+
+/// Cache statistics
+#[derive(Clone, Copy, Default, Debug)]
+pub struct CacheStatistics {
+	/// Hits
+	pub hits: usize,
+
+	/// Misses
+	pub misses: usize,
+
+	/// Dirty lines written back to the next level
+	pub writebacks: usize,
+}
+
+impl CacheStatistics {
+	/// Returns the hit ratio
+	pub fn hit_ratio(&self) -> f64 {
+		match self.hits + self.misses {
+			0 => 0.0,
+			accesses => self.hits as f64 / accesses as f64,
+		}
+	}
+}
+
+/// Cache line
+#[derive(Clone, Copy, Debug)]
+struct Line {
+	/// Address
+	addr: u64,
+
+	/// Whether the line was written to
+	dirty: bool,
+}
+
+/// Access kind
+#[derive(Clone, Copy, Debug)]
+enum AccessKind {
+	/// Read
+	Read,
+
+	/// Write
+	Write,
+
+	/// Writeback from the previous level
+	Writeback,
+}
+
+/// Output of [`Cache::access`]
+#[derive(Clone, Copy, Debug)]
+struct AccessOutput {
+	/// Line to fetch from the next level, on a miss
+	fill_addr: Option<u64>,
+
+	/// Dirty line to write back to the next level, on an eviction
+	writeback_addr: Option<u64>,
+}
@@ -1,4 +1,13 @@
 //! All classifiers
 
 // Modules
+pub mod arc;
+pub mod autonuma;
 pub mod hemem;
+pub mod lru_list;
+pub mod memtis;
+pub mod mglru;
+pub mod observer;
+pub mod placement;
+pub mod tiered;
+pub mod tpp;
@@ -0,0 +1,414 @@
+//! ARC classifier
+//!
+//! Manages the residency of the fastest memory as a cache of the slower memories,
+//! with either ARC (adaptive replacement cache) or 2Q.
+//!
+//! Pages are promoted to the fastest memory whenever they're accessed outside of
+//! it, with a page being demoted to make room if it's full. Both policies remember
+//! some of the demoted pages in ghost lists, to promote them to their frequency list
+//! if accessed again soon. ARC also uses it's ghost lists to adapt the target size of
+//! it's recency list.
+
+// Imports
+use {
+	super::{
+		hemem::{memories::MemIdx, statistics, PagePtr},
+		lru_list::LruList,
+		observer::Observer,
+		tiered::TieredMemory,
+	},
+	crate::sim,
+	anyhow::Context,
+	ftmemsim_util::FemtoDuration,
+	std::fmt,
+};
+
+/// ARC classifier
+#[derive(Debug)]
+pub struct Arc {
+	/// Tiered memory
+	tiered: TieredMemory,
+
+	/// Fastest memory
+	fast_mem_idx: MemIdx,
+
+	/// Capacity of the fastest memory (in pages)
+	capacity: usize,
+
+	/// Lists
+	lists: Lists,
+
+	/// Accesses to pages in the ghost lists
+	ghost_hits: usize,
+}
+
+impl Arc {
+	/// Creates an ARC classifier
+	///
+	/// # Errors
+	/// Returns an error if there are no memories, or if the 2Q fractions aren't within `0.0..=1.0`.
+	pub fn new(config: Config, tiered: TieredMemory) -> Result<Self, anyhow::Error> {
+		let (fast_mem_idx, fast_mem) = tiered.memories().iter().next().context("Must have a memory")?;
+		let capacity = fast_mem.page_capacity();
+
+		let lists = match config {
+			Config::Arc => Lists::Arc {
+				t1:     LruList::new(),
+				t2:     LruList::new(),
+				b1:     LruList::new(),
+				b2:     LruList::new(),
+				target: 0,
+			},
+			Config::TwoQ {
+				in_fraction,
+				out_fraction,
+			} => {
+				anyhow::ensure!(
+					(0.0..=1.0).contains(&in_fraction) && (0.0..=1.0).contains(&out_fraction),
+					"2Q fractions must be within 0.0 and 1.0"
+				);
+
+				Lists::TwoQ {
+					a1_in:    LruList::new(),
+					a1_out:   LruList::new(),
+					am:       LruList::new(),
+					in_size:  (in_fraction * capacity as f64) as usize,
+					out_size: (out_fraction * capacity as f64) as usize,
+				}
+			},
+		};
+
+		Ok(Self {
+			tiered,
+			fast_mem_idx,
+			capacity,
+			lists,
+			ghost_hits: 0,
+		})
+	}
+
+	/// Returns the statistics
+	pub fn statistics(&self) -> &statistics::Statistics {
+		self.tiered.statistics()
+	}
+
+	/// Handles an access to a page in the fastest memory
+	fn hit(&mut self, page_ptr: PagePtr) {
+		match &mut self.lists {
+			Lists::Arc { t1, t2, .. } => {
+				t1.remove(page_ptr);
+				t2.push_front(page_ptr);
+			},
+
+			// Note: The recency list is a FIFO, so it's pages aren't moved
+			Lists::TwoQ { am, .. } =>
+				if am.contains(page_ptr) {
+					am.push_front(page_ptr);
+				},
+		}
+	}
+
+	/// Handles an access to a page outside of the fastest memory, either in a slower
+	/// memory or, if `!is_mapped`, not yet mapped.
+	///
+	/// Makes room in the fastest memory and moves the page to it.
+	fn miss(&mut self, cur_time: u64, page_ptr: PagePtr, is_mapped: bool) -> Result<(), anyhow::Error> {
+		let capacity = self.capacity;
+		let frequent = match &mut self.lists {
+			Lists::Arc { t1, t2, b1, b2, target } => {
+				// If it's a ghost, adapt the target towards the list it was demoted from
+				let in_b1 = b1.contains(page_ptr);
+				let in_b2 = b2.contains(page_ptr);
+				match (in_b1, in_b2) {
+					(true, _) => {
+						*target = (*target + (b2.len() / b1.len()).max(1)).min(capacity);
+						b1.remove(page_ptr);
+					},
+					(_, true) => {
+						*target = target.saturating_sub((b1.len() / b2.len()).max(1));
+						b2.remove(page_ptr);
+					},
+
+					// Else keep the directory within twice the capacity
+					(false, false) => {
+						let recency_len = t1.len() + b1.len();
+						let total_len = recency_len + t2.len() + b2.len();
+						if recency_len >= capacity {
+							match t1.len() < capacity {
+								true => {
+									b1.pop_back();
+								},
+								false => {
+									let victim_ptr = t1.back().expect("Recency list was empty");
+									Self::demote_page(&mut self.tiered, cur_time, victim_ptr, self.fast_mem_idx)?;
+									t1.remove(victim_ptr);
+								},
+							}
+						} else if total_len >= 2 * capacity {
+							b2.pop_back();
+						}
+					},
+				}
+
+				// Then replace a page, if full
+				if self.tiered.memories().get(self.fast_mem_idx).is_full() {
+					let from_t1 = !t1.is_empty() && (t1.len() > *target || (in_b2 && t1.len() == *target));
+					let (list, ghost_list) = match from_t1 || t2.is_empty() {
+						true => (t1, b1),
+						false => (t2, b2),
+					};
+					let victim_ptr = list.back().context("Fastest memory had no pages")?;
+					Self::demote_page(&mut self.tiered, cur_time, victim_ptr, self.fast_mem_idx)?;
+					list.remove(victim_ptr);
+					ghost_list.push_front(victim_ptr);
+				}
+
+				in_b1 || in_b2
+			},
+
+			Lists::TwoQ {
+				a1_in,
+				a1_out,
+				am,
+				in_size,
+				out_size,
+			} => {
+				let in_a1_out = a1_out.remove(page_ptr);
+
+				// Reclaim from the recency list if it's over it's size, else from the frequency list
+				if self.tiered.memories().get(self.fast_mem_idx).is_full() {
+					match a1_in.len() > *in_size || am.is_empty() {
+						true => {
+							let victim_ptr = a1_in.back().context("Fastest memory had no pages")?;
+							Self::demote_page(&mut self.tiered, cur_time, victim_ptr, self.fast_mem_idx)?;
+							a1_in.remove(victim_ptr);
+							a1_out.push_front(victim_ptr);
+							while a1_out.len() > *out_size {
+								a1_out.pop_back();
+							}
+						},
+						false => {
+							let victim_ptr = am.back().expect("Frequency list was empty");
+							Self::demote_page(&mut self.tiered, cur_time, victim_ptr, self.fast_mem_idx)?;
+							am.remove(victim_ptr);
+						},
+					}
+				}
+
+				in_a1_out
+			},
+		};
+		self.ghost_hits += usize::from(frequent);
+
+		// Finally move the page to the fastest memory
+		match is_mapped {
+			true => self.tiered.migrate_page(cur_time, page_ptr, self.fast_mem_idx)?,
+			false => self.tiered.map_page(cur_time, page_ptr, self.fast_mem_idx)?,
+		}
+		match (&mut self.lists, frequent) {
+			(Lists::Arc { t2: list, .. } | Lists::TwoQ { am: list, .. }, true) |
+			(Lists::Arc { t1: list, .. } | Lists::TwoQ { a1_in: list, .. }, false) => list.push_front(page_ptr),
+		}
+
+		Ok(())
+	}
+
+	/// Demotes a page from the fastest memory to the nearest demotion target with space
+	fn demote_page(
+		tiered: &mut TieredMemory,
+		cur_time: u64,
+		page_ptr: PagePtr,
+		fast_mem_idx: MemIdx,
+	) -> Result<(), anyhow::Error> {
+		let dst_mem_idx = tiered
+			.memories()
+			.demotion_targets(fast_mem_idx)
+			.iter()
+			.copied()
+			.find(|&dst_mem_idx| !tiered.memories().get(dst_mem_idx).is_full())
+			.context("All demotion targets were full")?;
+
+		tiered.migrate_page(cur_time, page_ptr, dst_mem_idx)
+	}
+}
+
+impl sim::Classifier for Arc {
+	fn register_trace(&mut self, trace: sim::Trace) {
+		self.tiered.register_trace_record(trace.record.time);
+	}
+
+	fn handle_trace(&mut self, trace: sim::Trace) -> Result<(), anyhow::Error> {
+		tracing::trace!(?trace, "Received trace");
+		let page_ptr = PagePtr::new(trace.record.addr);
+		let cur_time = trace.record.time;
+
+		match self.tiered.page_mem(page_ptr) {
+			// If it's in the fastest memory, it's a hit
+			Some(mem_idx) if mem_idx == self.fast_mem_idx => {
+				self.hit(page_ptr);
+				self.tiered.access(
+					trace.record,
+					statistics::AccessMem::Resided(mem_idx),
+					FemtoDuration::ZERO,
+					0,
+					0,
+					false,
+				);
+			},
+
+			// Else promote it.
+			// Note: The access happens before the promotion, so it's served by the previous memory.
+			Some(mem_idx) => {
+				self.tiered.access(
+					trace.record,
+					statistics::AccessMem::Resided(mem_idx),
+					FemtoDuration::ZERO,
+					0,
+					0,
+					false,
+				);
+				if let Err(err) = self.miss(cur_time, page_ptr, true) {
+					tracing::trace!(?page_ptr, ?err, "Unable to promote page");
+				}
+			},
+
+			// Else map it, to the fastest memory if possible
+			None => {
+				let mem_idx = match self.miss(cur_time, page_ptr, false) {
+					Ok(()) => self.fast_mem_idx,
+					Err(err) => {
+						tracing::trace!(?page_ptr, ?err, "Unable to map page to fastest memory");
+						match self.tiered.map_page_first_fit(cur_time, page_ptr) {
+							Ok(mem_idx) => mem_idx,
+							Err(err) =>
+								return self
+									.tiered
+									.handle_oom(cur_time, page_ptr, err)
+									.context("Unable to map page"),
+						}
+					},
+				};
+				self.tiered.access(
+					trace.record,
+					statistics::AccessMem::Mapped(mem_idx),
+					FemtoDuration::ZERO,
+					0,
+					0,
+					false,
+				);
+			},
+		}
+
+		Ok(())
+	}
+
+	fn finish(&mut self) {
+		self.tiered.finish();
+	}
+
+	fn add_observer(&mut self, observer: impl Observer + 'static) {
+		self.tiered.add_observer(observer);
+	}
+
+	fn fmt_debug(&mut self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+		// Note: Start with a newline, since we're a multi-line output
+		f.pad("\n")?;
+
+		self.tiered.fmt_debug(f)?;
+		match &self.lists {
+			Lists::Arc { t1, t2, b1, b2, target } => writeln!(
+				f,
+				"ARC: {} recent, {} frequent, {} / {} ghosts, {target} target",
+				t1.len(),
+				t2.len(),
+				b1.len(),
+				b2.len()
+			)?,
+			Lists::TwoQ { a1_in, a1_out, am, .. } => writeln!(
+				f,
+				"2Q: {} recent, {} frequent, {} ghosts",
+				a1_in.len(),
+				am.len(),
+				a1_out.len()
+			)?,
+		}
+
+		writeln!(f, "Ghost hits: {}", self.ghost_hits)
+	}
+
+	fn debug_stats(&mut self) -> serde_json::Value {
+		let mut stats = self.tiered.debug_stats();
+		stats["lists"] = match &self.lists {
+			Lists::Arc { t1, t2, b1, b2, target } => serde_json::json!({
+				"t1": t1.len(),
+				"t2": t2.len(),
+				"b1": b1.len(),
+				"b2": b2.len(),
+				"target": target,
+			}),
+			Lists::TwoQ { a1_in, a1_out, am, .. } => serde_json::json!({
+				"a1_in": a1_in.len(),
+				"a1_out": a1_out.len(),
+				"am": am.len(),
+			}),
+		};
+		stats["ghost_hits"] = serde_json::Value::from(self.ghost_hits);
+		stats
+	}
+}
+
+/// Configuration
+#[derive(Clone, Debug)]
+pub enum Config {
+	/// ARC
+	Arc,
+
+	/// 2Q
+	TwoQ {
+		/// Size of the recency list, as a fraction of the fastest memory's capacity
+		in_fraction: f64,
+
+		/// Size of the ghost list, as a fraction of the fastest memory's capacity
+		out_fraction: f64,
+	},
+}
+
+/// Lists
+#[derive(Debug)]
+enum Lists {
+	/// ARC
+	Arc {
+		/// Pages accessed once recently
+		t1: LruList<PagePtr>,
+
+		/// Pages accessed at least twice recently
+		t2: LruList<PagePtr>,
+
+		/// Ghosts of pages demoted from `t1`
+		b1: LruList<PagePtr>,
+
+		/// Ghosts of pages demoted from `t2`
+		b2: LruList<PagePtr>,
+
+		/// Target size of `t1`
+		target: usize,
+	},
+
+	/// 2Q
+	TwoQ {
+		/// Pages accessed once recently, in FIFO order
+		a1_in: LruList<PagePtr>,
+
+		/// Ghosts of pages demoted from `a1_in`
+		a1_out: LruList<PagePtr>,
+
+		/// Pages accessed again after being demoted from `a1_in`
+		am: LruList<PagePtr>,
+
+		/// Target size of `a1_in`
+		in_size: usize,
+
+		/// Max size of `a1_out`
+		out_size: usize,
+	},
+}
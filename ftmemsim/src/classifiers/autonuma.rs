@@ -0,0 +1,336 @@
+//! AutoNUMA classifier
+//!
+//! Models Linux's AutoNUMA memory tiering: Every scan period, a window of pages
+//! in each memory with promotion targets is "unmapped", so that their next access
+//! takes a hint fault. A page that hint faults soon enough after being unmapped is
+//! considered hot and is promoted, as long as the promotion rate limit allows it.
+//!
+//! New pages are allocated in the fastest memory with space, and memories that are
+//! full when promoting demote their least recently used page to make room.
+//!
+//! Note: We see every access, so, unlike the kernel, we track the least recently used
+//!       page of each memory exactly.
+
+// Imports
+use {
+	super::{
+		hemem::{
+			memories::{self, MemIdx},
+			statistics,
+			PagePtr,
+		},
+		lru_list::LruList,
+		observer::Observer,
+		tiered::TieredMemory,
+	},
+	crate::sim,
+	anyhow::Context,
+	ftmemsim_util::FemtoDuration,
+	std::{collections::BTreeMap, fmt},
+};
+
+/// AutoNUMA classifier
+#[derive(Debug)]
+pub struct AutoNuma {
+	/// Config
+	config: Config,
+
+	/// Tiered memory
+	tiered: TieredMemory,
+
+	/// Time each page was unmapped at, if it's waiting for a hint fault
+	unmapped_pages: BTreeMap<PagePtr, u64>,
+
+	/// LRU list of each memory
+	lru_lists: BTreeMap<MemIdx, LruList<PagePtr>>,
+
+	/// Next time to scan
+	next_scan_time: Option<u64>,
+
+	/// Last page scanned in each memory
+	scan_cursors: BTreeMap<MemIdx, PagePtr>,
+
+	/// Promotion rate limit window
+	rate_limit_window: RateLimitWindow,
+
+	/// Hint faults
+	hint_faults: usize,
+
+	/// Promotions skipped due to the rate limit
+	rate_limited_promotions: usize,
+}
+
+impl AutoNuma {
+	/// Promotion rate limit window (in trace time, which we assume to be nanoseconds)
+	pub const RATE_LIMIT_WINDOW: u64 = FemtoDuration::NANOS_PER_SEC;
+
+	/// Creates an AutoNUMA classifier
+	///
+	/// # Errors
+	/// Returns an error if the scan period is 0, or if the rate limit is negative.
+	pub fn new(config: Config, tiered: TieredMemory) -> Result<Self, anyhow::Error> {
+		anyhow::ensure!(config.scan_period != 0, "Scan period must not be 0");
+		anyhow::ensure!(
+			config
+				.promote_rate_limit_mb_per_sec
+				.is_none_or(|rate_limit| rate_limit >= 0.0),
+			"Promotion rate limit must not be negative"
+		);
+
+		Ok(Self {
+			config,
+			tiered,
+			unmapped_pages: BTreeMap::new(),
+			lru_lists: BTreeMap::new(),
+			next_scan_time: None,
+			scan_cursors: BTreeMap::new(),
+			rate_limit_window: RateLimitWindow::default(),
+			hint_faults: 0,
+			rate_limited_promotions: 0,
+		})
+	}
+
+	/// Returns the statistics
+	pub fn statistics(&self) -> &statistics::Statistics {
+		self.tiered.statistics()
+	}
+
+	/// Scans, if it's been long enough since the last scan.
+	///
+	/// Unmaps the next `scan_pages` pages, by address, of each memory with promotion targets.
+	fn scan(&mut self, cur_time: u64) {
+		let next_scan_time = *self.next_scan_time.get_or_insert(cur_time + self.config.scan_period);
+		if cur_time < next_scan_time {
+			return;
+		}
+		self.next_scan_time = Some(cur_time + self.config.scan_period);
+
+		let mem_idxs = self
+			.tiered
+			.memories()
+			.iter()
+			.map(|(mem_idx, _)| mem_idx)
+			.filter(|&mem_idx| !self.tiered.memories().promotion_targets(mem_idx).is_empty())
+			.collect::<Vec<_>>();
+		for mem_idx in mem_idxs {
+			let cursor = self.scan_cursors.get(&mem_idx).copied();
+			let page_ptrs = self.tiered.mem_pages_after(mem_idx, cursor, self.config.scan_pages);
+
+			// Note: Pages that were already unmapped keep their original unmap time
+			for &page_ptr in &page_ptrs {
+				self.unmapped_pages.entry(page_ptr).or_insert(cur_time);
+			}
+			if let Some(&page_ptr) = page_ptrs.last() {
+				self.scan_cursors.insert(mem_idx, page_ptr);
+			}
+		}
+	}
+
+	/// Handles a hint fault on a page in memory `mem_idx`, unmapped at `unmapped_time`.
+	///
+	/// Promotes the page if it's hot and the rate limit allows it.
+	fn hint_fault(&mut self, cur_time: u64, page_ptr: PagePtr, mem_idx: MemIdx, unmapped_time: u64) {
+		self.hint_faults += 1;
+
+		// Only promote pages accessed soon enough after being unmapped
+		if let Some(hot_threshold) = self.config.hot_threshold {
+			if cur_time - unmapped_time > hot_threshold {
+				return;
+			}
+		}
+
+		// Then check the rate limit
+		let window_start = cur_time - cur_time % Self::RATE_LIMIT_WINDOW;
+		if window_start != self.rate_limit_window.start {
+			self.rate_limit_window = RateLimitWindow {
+				start:          window_start,
+				promoted_bytes: 0,
+			};
+		}
+		if let Some(rate_limit) = self.config.promote_rate_limit_mb_per_sec {
+			let window_limit_bytes =
+				rate_limit * 1e6 * (Self::RATE_LIMIT_WINDOW as f64 / FemtoDuration::NANOS_PER_SEC as f64);
+			if (self.rate_limit_window.promoted_bytes + memories::PAGE_BYTES) as f64 > window_limit_bytes {
+				self.rate_limited_promotions += 1;
+				return;
+			}
+		}
+
+		match self.promote_page(cur_time, page_ptr, mem_idx) {
+			Ok(()) => self.rate_limit_window.promoted_bytes += memories::PAGE_BYTES,
+			Err(err) => tracing::trace!(?page_ptr, ?err, "Unable to promote page"),
+		}
+	}
+
+	/// Promotes a page from memory `mem_idx` to the nearest promotion target,
+	/// demoting it's least recently used page first if it's full.
+	fn promote_page(&mut self, cur_time: u64, page_ptr: PagePtr, mem_idx: MemIdx) -> Result<(), anyhow::Error> {
+		let dst_mem_idx = *self
+			.tiered
+			.memories()
+			.promotion_targets(mem_idx)
+			.first()
+			.context("Memory has no promotion targets")?;
+		if self.tiered.memories().get(dst_mem_idx).is_full() {
+			self.demote_lru_page(cur_time, dst_mem_idx)
+				.context("Unable to make room in promotion target")?;
+		}
+
+		self.migrate_page(cur_time, page_ptr, mem_idx, dst_mem_idx)
+	}
+
+	/// Demotes the least recently used page of memory `mem_idx` to the nearest demotion target with space
+	fn demote_lru_page(&mut self, cur_time: u64, mem_idx: MemIdx) -> Result<(), anyhow::Error> {
+		let dst_mem_idx = self
+			.tiered
+			.memories()
+			.demotion_targets(mem_idx)
+			.iter()
+			.copied()
+			.find(|&dst_mem_idx| !self.tiered.memories().get(dst_mem_idx).is_full())
+			.context("All demotion targets were full")?;
+		let page_ptr = self
+			.lru_lists
+			.get(&mem_idx)
+			.and_then(LruList::back)
+			.context("Memory had no pages")?;
+
+		self.migrate_page(cur_time, page_ptr, mem_idx, dst_mem_idx)
+	}
+
+	/// Migrates a page from memory `src_mem_idx` to `dst_mem_idx`, as it's most recently used page
+	fn migrate_page(
+		&mut self,
+		cur_time: u64,
+		page_ptr: PagePtr,
+		src_mem_idx: MemIdx,
+		dst_mem_idx: MemIdx,
+	) -> Result<(), anyhow::Error> {
+		self.tiered.migrate_page(cur_time, page_ptr, dst_mem_idx)?;
+		self.lru_lists.entry(src_mem_idx).or_default().remove(page_ptr);
+		self.lru_lists.entry(dst_mem_idx).or_default().push_front(page_ptr);
+		self.unmapped_pages.remove(&page_ptr);
+
+		Ok(())
+	}
+}
+
+impl sim::Classifier for AutoNuma {
+	fn register_trace(&mut self, trace: sim::Trace) {
+		self.tiered.register_trace_record(trace.record.time);
+	}
+
+	fn handle_trace(&mut self, trace: sim::Trace) -> Result<(), anyhow::Error> {
+		tracing::trace!(?trace, "Received trace");
+		let page_ptr = PagePtr::new(trace.record.addr);
+		let cur_time = trace.record.time;
+
+		self.scan(cur_time);
+
+		// Map the page if it doesn't exist
+		let Some(mem_idx) = self.tiered.page_mem(page_ptr) else {
+			let mem_idx = match self.tiered.map_page_first_fit(cur_time, page_ptr) {
+				Ok(mem_idx) => mem_idx,
+				Err(err) =>
+					return self
+						.tiered
+						.handle_oom(cur_time, page_ptr, err)
+						.context("Unable to map page"),
+			};
+			self.lru_lists.entry(mem_idx).or_default().push_front(page_ptr);
+			self.tiered.access(
+				trace.record,
+				statistics::AccessMem::Mapped(mem_idx),
+				FemtoDuration::ZERO,
+				0,
+				0,
+				false,
+			);
+			return Ok(());
+		};
+
+		// Else check for a hint fault, which pays it's latency on top of the access.
+		// Note: The access happens before the promotion, so it's served by the previous memory.
+		self.lru_lists.entry(mem_idx).or_default().push_front(page_ptr);
+		let extra_latency = match self.unmapped_pages.remove(&page_ptr) {
+			Some(unmapped_time) => {
+				self.hint_fault(cur_time, page_ptr, mem_idx, unmapped_time);
+				self.config.hint_fault_latency
+			},
+			None => FemtoDuration::ZERO,
+		};
+
+		self.tiered.access(
+			trace.record,
+			statistics::AccessMem::Resided(mem_idx),
+			extra_latency,
+			0,
+			0,
+			false,
+		);
+
+		Ok(())
+	}
+
+	fn finish(&mut self) {
+		self.tiered.finish();
+	}
+
+	fn add_observer(&mut self, observer: impl Observer + 'static) {
+		self.tiered.add_observer(observer);
+	}
+
+	fn fmt_debug(&mut self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+		// Note: Start with a newline, since we're a multi-line output
+		f.pad("\n")?;
+
+		self.tiered.fmt_debug(f)?;
+		writeln!(
+			f,
+			"Hint faults: {} ({} unmapped pages, {} rate limited promotions)",
+			self.hint_faults,
+			self.unmapped_pages.len(),
+			self.rate_limited_promotions
+		)
+	}
+
+	fn debug_stats(&mut self) -> serde_json::Value {
+		let mut stats = self.tiered.debug_stats();
+		stats["hint_faults"] = serde_json::Value::from(self.hint_faults);
+		stats["rate_limited_promotions"] = serde_json::Value::from(self.rate_limited_promotions);
+		stats
+	}
+}
+
+/// Configuration
+#[derive(Clone, Debug)]
+pub struct Config {
+	/// Period between each scan (in trace time)
+	pub scan_period: u64,
+
+	/// Pages unmapped on each memory, per scan
+	pub scan_pages: usize,
+
+	/// Latency of a hint fault
+	pub hint_fault_latency: FemtoDuration,
+
+	/// Max time between unmapping a page and it's hint fault for it to be promoted (in trace time).
+	///
+	/// If `None`, all hint faults may promote.
+	pub hot_threshold: Option<u64>,
+
+	/// Promotion rate limit (in MB/s).
+	///
+	/// If `None`, promotions are unlimited.
+	pub promote_rate_limit_mb_per_sec: Option<f64>,
+}
+
+/// Promotion rate limit window
+#[derive(Clone, Copy, Default, Debug)]
+struct RateLimitWindow {
+	/// Start time
+	start: u64,
+
+	/// Bytes promoted within the window
+	promoted_bytes: u64,
+}
@@ -1,22 +1,30 @@
 //! Hemem classifier
 
 // Modules
+pub mod hotness;
 pub mod memories;
 pub mod page_table;
 pub mod statistics;
+pub mod thp;
+pub mod tlb;
 
 // Exports
 pub use self::{
+	hotness::{Hotness, HotnessEstimator},
 	memories::{Memories, Memory},
-	page_table::{Page, PagePtr, PageTable},
+	page_table::{ColdPageOptions, ColdPageSelection, Page, PagePtr, PageTable, TemperatureWeights},
 	statistics::Statistics,
+	thp::Thp,
+	tlb::Tlb,
 };
 
 // Imports
 use {
 	self::memories::MemIdx,
+	super::observer::{Observer, Observers},
 	crate::{pin_trace, sim},
 	anyhow::Context,
+	ftmemsim_util::FemtoDuration,
 	std::fmt,
 };
 
@@ -34,37 +42,85 @@ pub struct HeMem {
 
 	/// Statistics
 	statistics: Statistics,
+
+	/// TLB, if any
+	tlb: Option<Tlb>,
+
+	/// Transparent huge pages, if any
+	thp: Option<Thp>,
+
+	/// Observers
+	observers: Observers,
 }
 
 impl HeMem {
 	/// Creates a hemem classifier
-	pub fn new(config: Config, memories: Vec<Memory>) -> Self {
-		Self {
-			config,
-			memories: Memories::new(memories),
-			page_table: PageTable::new(),
-			statistics: Statistics::new(),
+	///
+	/// If `swap` is `Some`, it's used as a swap memory below the slowest memory.
+	/// It should have an unlimited capacity.
+	///
+	/// If `tlb` is `Some`, each access is first translated by it, and it's misses
+	/// pay the page walk latency.
+	///
+	/// If `thp` is `Some`, it decides the size of the page each access is translated
+	/// with, and each split and collapse pays it's latency.
+	///
+	/// # Errors
+	/// Returns an error if the migration targets of `memories` are invalid, if
+	/// `tlb` has no entries for the page sizes of `thp`, or if the demotion batch is 0.
+	pub fn new(
+		config: Config,
+		memories: Vec<Memory>,
+		swap: Option<Memory>,
+		tlb: Option<Tlb>,
+		thp: Option<Thp>,
+	) -> Result<Self, anyhow::Error> {
+		anyhow::ensure!(config.demotion_batch != 0, "Demotion batch must not be 0");
+		if let (Some(tlb), Some(_)) = (&tlb, &thp) {
+			anyhow::ensure!(
+				tlb.has_page_size(Thp::PAGE_SIZE) && tlb.has_page_size(Thp::HUGE_PAGE_SIZE),
+				"TLB must have entries for both 4KiB and 2MiB pages when using transparent huge pages"
+			);
 		}
+
+		Ok(Self {
+			memories: Memories::new(memories, swap).context("Unable to create memories")?,
+			page_table: PageTable::new(),
+			statistics: Statistics::new(config.epoch_length, config.access_log),
+			tlb,
+			thp,
+			config,
+			observers: Observers::new(),
+		})
 	}
 
 	/// Maps a page to the first available memory and returns it.
 	///
+	/// If all memories are full and we're swapping when out of memory, evicts
+	/// a page from the slowest memory to swap first.
+	///
 	/// # Errors
 	/// Returns an error if unable to insert
 	///
 	/// # Panics
 	/// Panics if the page is already mapped.
-	pub fn map_page(&mut self, page_ptr: PagePtr) -> Result<MemIdx, anyhow::Error> {
+	pub fn map_page(&mut self, cur_time: u64, page_ptr: PagePtr) -> Result<MemIdx, anyhow::Error> {
 		if self.page_table.contains(page_ptr) {
 			panic!("Page is already mapped: {page_ptr:?}");
 		}
 
+		let swap_mem_idx = self.memories.swap_memory();
 		for (mem_idx, mem) in self.memories.iter_mut() {
+			// Note: Pages are only mapped to swap after evicting
+			if Some(mem_idx) == swap_mem_idx {
+				continue;
+			}
+
 			// Try to reserve a page on this memory
 			match mem.reserve_page() {
 				// If we got it, add the page to the page table
 				Ok(()) => {
-					let page = Page::new(page_ptr, mem_idx);
+					let page = Page::new(page_ptr, mem_idx, self.config.hotness);
 					self.page_table.insert(page).expect("Unable to insert unmapped page");
 					return Ok(mem_idx);
 				},
@@ -77,37 +133,109 @@ impl HeMem {
 			}
 		}
 
-		// If we got here, all memories were full
-		anyhow::bail!("All memories were full");
+		// If we got here, all memories were full, so evict a page to swap, if we can
+		match self.config.oom_behavior {
+			OomBehavior::Error | OomBehavior::Drop => anyhow::bail!("All memories were full"),
+			OomBehavior::Swap => {
+				let mem_idx = self.memories.slowest_memory();
+				self.evict_page(cur_time, mem_idx)
+					.context("Unable to evict page to swap")?;
+
+				self.memories
+					.get_mut(mem_idx)
+					.reserve_page()
+					.expect("Just evicted a page");
+				let page = Page::new(page_ptr, mem_idx, self.config.hotness);
+				self.page_table.insert(page).expect("Unable to insert unmapped page");
+				Ok(mem_idx)
+			},
+		}
 	}
 
-	/// Cools a memory by (at most) `count` pages.
+	/// Evicts a page from memory `mem_idx` to swap.
+	///
+	/// Prefers cold pages, but evicts a hot page if there are none.
+	///
+	/// # Errors
+	/// Returns an error if there is no swap memory or if `mem_idx` is empty.
+	pub fn evict_page(&mut self, cur_time: u64, mem_idx: MemIdx) -> Result<(), anyhow::Error> {
+		let swap_mem_idx = self.memories.swap_memory().context("No swap memory")?;
+
+		let cold_page_ptr = self.cold_pages(cur_time, mem_idx, 1).pop();
+		let page_ptr = match cold_page_ptr {
+			Some(page_ptr) => page_ptr,
+			// Note: We pick the lowest address, so it doesn't depend on the order the pages arrived in
+			None => self
+				.page_table
+				.mem_pages(mem_idx)
+				.min()
+				.context("Memory had no pages to evict")?,
+		};
+
+		self.migrate_page(cur_time, page_ptr, swap_mem_idx)
+	}
+
+	/// Cools a memory by (at most) `config.demotion_batch` pages.
 	///
 	/// Returns if any pages were cooled
 	///
 	/// # Panics
 	/// Panics if `mem_idx` is an invalid memory index
-	// TODO: Cool more than just 1 page at a time?
 	pub fn cool_memory(&mut self, cur_time: u64, mem_idx: MemIdx) -> bool {
-		// If there's isn't slower memory than `mem_idx`, we can't cool it
-		if self.memories.slower_memory(mem_idx).is_none() {
+		// If there's isn't slower memory than `mem_idx`, we can't cool it.
+		// Note: Since we're under memory pressure, memories without any demotion
+		//       targets may be cooled to swap.
+		let swap_mem_idx = self
+			.memories
+			.swap_memory()
+			.filter(|&swap_mem_idx| swap_mem_idx != mem_idx);
+		let dst_mem_idxs = match self.memories.demotion_targets(mem_idx) {
+			[] => swap_mem_idx.into_iter().collect::<Vec<_>>(),
+			dst_mem_idxs => dst_mem_idxs.to_vec(),
+		};
+		if dst_mem_idxs.is_empty() {
 			return false;
 		}
 
-		// Get a cold page to cool, else we can't cool
-		let Some(page_ptr) = self
-			.page_table
-			.cold_pages(self.config.read_hot_threshold, self.config.write_hot_threshold, mem_idx)
-			.next()
-		else {
-			return false;
+		// Then try to cool a batch of cold pages
+		let mut pages_cooled = 0;
+		for page_ptr in self.cold_pages(cur_time, mem_idx, self.config.demotion_batch) {
+			match self.migrate_page_to_any(cur_time, page_ptr, &dst_mem_idxs) {
+				Ok(()) => pages_cooled += 1,
+				Err(err) => tracing::trace!(?page_ptr, ?err, "Unable to cool page"),
+			}
+		}
+
+		pages_cooled != 0
+	}
+
+	/// Returns (at most) `count` cold pages in memory `mem_idx`, in the configured selection order
+	fn cold_pages(&self, cur_time: u64, mem_idx: MemIdx, count: usize) -> Vec<PagePtr> {
+		let options = ColdPageOptions {
+			read_hot_threshold:  self.config.read_hot_threshold,
+			write_hot_threshold: self.config.write_hot_threshold,
+			selection:           self.config.cold_page_selection,
+			weights:             self.config.temperature_weights,
 		};
+		self.page_table.cold_pages(cur_time, mem_idx, options, count)
+	}
 
-		// Then try to cool the page
-		match self.cool_page(cur_time, page_ptr) {
-			Ok(()) => true,
-			Err(_) => false,
+	/// Swaps a page in from swap to the slowest memory, evicting a page if it's full.
+	///
+	/// # Errors
+	/// Returns an error if unable to evict a page or migrate the page.
+	///
+	/// # Panics
+	/// Panics if `page_ptr` isn't a mapped page.
+	pub fn swap_in_page(&mut self, cur_time: u64, page_ptr: PagePtr) -> Result<(), anyhow::Error> {
+		let mem_idx = self.memories.slowest_memory();
+		if self.memories.get(mem_idx).is_full() {
+			self.evict_page(cur_time, mem_idx)
+				.context("Unable to evict page to swap")?;
 		}
+
+		self.migrate_page(cur_time, page_ptr, mem_idx)
+			.context("Unable to migrate page out of swap")
 	}
 
 	/// Migrates a page, possibly cooling the destination if full.
@@ -126,13 +254,12 @@ impl HeMem {
 			// If we managed to, move the page's memory
 			Ok(()) => {
 				self.page_table.move_mem(page_ptr, dst_mem_idx);
-
-				self.statistics
-					.register_page_migration(page_ptr, statistics::PageMigration {
-						time:         cur_time,
-						prev_mem_idx: Some(src_mem_idx),
-						cur_mem_idx:  dst_mem_idx,
-					});
+				self.register_page_migration(page_ptr, statistics::PageMigration {
+					time:         cur_time,
+					prev_mem_idx: Some(src_mem_idx),
+					cur_mem_idx:  dst_mem_idx,
+					kind:         self.memories.migration_kind(src_mem_idx, dst_mem_idx),
+				});
 			},
 
 			// Else try to cool the destination memory first, then try again
@@ -152,12 +279,12 @@ impl HeMem {
 							.migrate_page(src_mem_idx, dst_mem_idx)
 							.expect("Just freed some pages when cooling");
 						self.page_table.move_mem(page_ptr, dst_mem_idx);
-						self.statistics
-							.register_page_migration(page_ptr, statistics::PageMigration {
-								time:         cur_time,
-								prev_mem_idx: Some(src_mem_idx),
-								cur_mem_idx:  dst_mem_idx,
-							});
+						self.register_page_migration(page_ptr, statistics::PageMigration {
+							time:         cur_time,
+							prev_mem_idx: Some(src_mem_idx),
+							cur_mem_idx:  dst_mem_idx,
+							kind:         self.memories.migration_kind(src_mem_idx, dst_mem_idx),
+						});
 					},
 
 					// Else we can't move it
@@ -169,6 +296,39 @@ impl HeMem {
 		Ok(())
 	}
 
+	/// Migrates a page to the first memory in `dst_mem_idxs` it can be migrated to.
+	///
+	/// # Errors
+	/// Returns an error if unable to migrate the page to any of `dst_mem_idxs`.
+	///
+	/// # Panics
+	/// Panics if `page_ptr` isn't a mapped page.
+	/// Panics if any of `dst_mem_idxs` are invalid memory indexes.
+	pub fn migrate_page_to_any(
+		&mut self,
+		cur_time: u64,
+		page_ptr: PagePtr,
+		dst_mem_idxs: &[MemIdx],
+	) -> Result<(), anyhow::Error> {
+		let mut last_err = None;
+		for &dst_mem_idx in dst_mem_idxs {
+			match self.migrate_page(cur_time, page_ptr, dst_mem_idx) {
+				Ok(()) => return Ok(()),
+				Err(err) => {
+					tracing::trace!(
+						?page_ptr,
+						?dst_mem_idx,
+						?err,
+						"Unable to migrate page, trying next target"
+					);
+					last_err = Some(err);
+				},
+			}
+		}
+
+		Err(last_err.unwrap_or_else(|| anyhow::anyhow!("No memories to migrate to")))
+	}
+
 	/// Cools a page.
 	///
 	/// # Errors
@@ -179,14 +339,14 @@ impl HeMem {
 	pub fn cool_page(&mut self, cur_time: u64, page_ptr: PagePtr) -> Result<(), anyhow::Error> {
 		let page = self.page_table.get_mut(page_ptr).expect("Page wasn't in page table");
 
-		// Get the new memory index in the slower memory
-		let dst_mem_idx = match self.memories.slower_memory(page.mem_idx()) {
-			Some(mem_idx) => mem_idx,
-			None => anyhow::bail!("Page is already in the slowest memory"),
+		// Get the slower memories to demote to
+		let dst_mem_idxs = match self.memories.demotion_targets(page.mem_idx()) {
+			[] => anyhow::bail!("Page is already in the slowest memory"),
+			dst_mem_idxs => dst_mem_idxs.to_vec(),
 		};
 
 		// Then try to migrate it
-		self.migrate_page(cur_time, page_ptr, dst_mem_idx)
+		self.migrate_page_to_any(cur_time, page_ptr, &dst_mem_idxs)
 			.context("Unable to migrate page to slower memory")
 	}
 
@@ -200,15 +360,14 @@ impl HeMem {
 	pub fn warm_page(&mut self, cur_time: u64, page_ptr: PagePtr) -> Result<(), anyhow::Error> {
 		let page = self.page_table.get_mut(page_ptr).expect("Page wasn't in page table");
 
-		// Get the new memory index in the faster memory
-		let src_mem_idx = page.mem_idx();
-		let dst_mem_idx = match self.memories.faster_memory(src_mem_idx) {
-			Some(mem_idx) => mem_idx,
-			None => anyhow::bail!("Page is already in the hottest memory"),
+		// Get the faster memories to promote to
+		let dst_mem_idxs = match self.memories.promotion_targets(page.mem_idx()) {
+			[] => anyhow::bail!("Page is already in the hottest memory"),
+			dst_mem_idxs => dst_mem_idxs.to_vec(),
 		};
 
 		// Then try to migrate it
-		self.migrate_page(cur_time, page_ptr, dst_mem_idx)
+		self.migrate_page_to_any(cur_time, page_ptr, &dst_mem_idxs)
 			.context("Unable to migrate page to faster memory")
 	}
 
@@ -216,9 +375,47 @@ impl HeMem {
 	pub fn statistics(&self) -> &Statistics {
 		&self.statistics
 	}
+
+	/// Registers an access on the statistics and notifies all observers
+	fn register_access(&mut self, access: statistics::Access) {
+		self.statistics.register_access(access);
+		self.observers.on_access(&access);
+		self.observers.notify_finished_epochs(&self.statistics);
+	}
+
+	/// Registers a page migration on the statistics and notifies all observers.
+	///
+	/// Migrations without a previous memory are notified as a mapping, the others
+	/// also register their traffic on both memories.
+	fn register_page_migration(&mut self, page_ptr: PagePtr, page_migration: statistics::PageMigration) {
+		if let Some(prev_mem_idx) = page_migration.prev_mem_idx {
+			let window = self.config.bandwidth_window;
+			self.memories.get_mut(prev_mem_idx).register_traffic(
+				page_migration.time,
+				window,
+				memories::TrafficKind::Read,
+				memories::PAGE_BYTES,
+			);
+			self.memories.get_mut(page_migration.cur_mem_idx).register_traffic(
+				page_migration.time,
+				window,
+				memories::TrafficKind::Write,
+				memories::PAGE_BYTES,
+			);
+		}
+
+		self.observers.on_page_migration(page_ptr, &page_migration);
+		self.statistics.register_page_migration(page_ptr, page_migration);
+		self.observers.notify_finished_epochs(&self.statistics);
+	}
 }
 
 impl sim::Classifier for HeMem {
+	fn register_trace(&mut self, trace: sim::Trace) {
+		self.statistics.register_trace_record(trace.record.time);
+		self.observers.notify_finished_epochs(&self.statistics);
+	}
+
 	fn handle_trace(&mut self, trace: sim::Trace) -> Result<(), anyhow::Error> {
 		tracing::trace!(?trace, "Received trace");
 		let page_ptr = PagePtr::new(trace.record.addr);
@@ -227,38 +424,69 @@ impl sim::Classifier for HeMem {
 		let page_prev_mem_idx = self.page_table.get_mut(page_ptr).map(|page| page.mem_idx());
 		if !self.page_table.contains(page_ptr) {
 			tracing::trace!(?page_ptr, "Mapping page");
-			let page_mem_idx = self.map_page(page_ptr).context("Unable to map page")?;
+			let page_mem_idx = match self.map_page(trace.record.time, page_ptr) {
+				Ok(mem_idx) => mem_idx,
+				Err(err) => match self.config.oom_behavior {
+					OomBehavior::Drop => {
+						tracing::trace!(?page_ptr, ?err, "Unable to map page, dropping access");
+						self.statistics.register_dropped_access(trace.record.time);
+						self.observers.notify_finished_epochs(&self.statistics);
+						return Ok(());
+					},
+					OomBehavior::Error | OomBehavior::Swap => return Err(err).context("Unable to map page"),
+				},
+			};
 
 			// Register an initial page migration when mapping
-			self.statistics
-				.register_page_migration(page_ptr, statistics::PageMigration {
-					time:         trace.record.time,
-					prev_mem_idx: None,
-					cur_mem_idx:  page_mem_idx,
-				});
+			self.register_page_migration(page_ptr, statistics::PageMigration {
+				time:         trace.record.time,
+				prev_mem_idx: None,
+				cur_mem_idx:  page_mem_idx,
+				kind:         None,
+			});
 		};
-		let page = self.page_table.get_mut(page_ptr).expect("Page wasn't in page table");
-		let page_was_hot = page.is_hot(self.config.read_hot_threshold, self.config.write_hot_threshold);
-		let page_prev_temperature = page.temperature();
 
+		// If the page is in swap, swap it in
+		let swap_mem_idx = self.memories.swap_memory();
+		let page_swapped_in = page_prev_mem_idx.is_some() && page_prev_mem_idx == swap_mem_idx;
+		if page_swapped_in {
+			tracing::trace!(?page_ptr, "Swapping page in");
+			self.swap_in_page(trace.record.time, page_ptr)
+				.context("Unable to swap in page")?;
+		}
+
+		let page = self.page_table.get_mut(page_ptr).expect("Page wasn't in page table");
+		let page_was_hot = page.is_hot(
+			trace.record.time,
+			self.config.read_hot_threshold,
+			self.config.write_hot_threshold,
+		);
+		let page_prev_temperature = page.temperature(trace.record.time, self.config.temperature_weights);
 
 		// Register the access on the page
 		match trace.record.kind {
-			pin_trace::RecordAccessKind::Read => page.register_read_access(),
-			pin_trace::RecordAccessKind::Write => page.register_write_access(),
+			pin_trace::RecordAccessKind::Read => page.register_read_access(trace.record.time),
+			pin_trace::RecordAccessKind::Write => page.register_write_access(trace.record.time),
 		};
 
 		// If the page is over the threshold, cool all pages
-		let caused_cooling = page.over_threshold(self.config.global_cooling_threshold);
+		// Note: Estimates that decay on their own don't need to be cooled.
+		let caused_cooling = !self.config.hotness.decays() &&
+			page.over_threshold(trace.record.time, self.config.global_cooling_threshold);
 		if caused_cooling {
 			self.page_table.cool_all_pages();
+			self.observers.on_cool_all(trace.record.time);
 		}
 
 		// Finally check if it's still hot and adjust if necessary
 		let page = self.page_table.get_mut(page_ptr).expect("Page wasn't in page table");
-		let page_is_hot = page.is_hot(self.config.read_hot_threshold, self.config.write_hot_threshold);
+		let page_is_hot = page.is_hot(
+			trace.record.time,
+			self.config.read_hot_threshold,
+			self.config.write_hot_threshold,
+		);
 		let page_cur_mem_idx = page.mem_idx();
-		let page_cur_temperature = page.temperature();
+		let page_cur_temperature = page.temperature(trace.record.time, self.config.temperature_weights);
 
 		// If the page isn't hot and it was hot, cool it
 		if !page_is_hot && page_was_hot {
@@ -276,33 +504,113 @@ impl sim::Classifier for HeMem {
 			}
 		}
 
+		// Track the access's region, if we have transparent huge pages
+		let thp_event = self
+			.thp
+			.as_mut()
+			.and_then(|thp| thp.access(trace.record.time, trace.record.addr));
+		let thp_latency = thp_event
+			.as_ref()
+			.map_or(FemtoDuration::ZERO, |thp_event| thp_event.cost);
+		if let Some(thp_event) = thp_event {
+			tracing::trace!(?thp_event, "Huge page split or collapsed");
+			self.statistics.register_thp_event(thp_event);
+		}
+
+		// Translate the access, if we have a tlb
+		let page_size = self.thp.as_ref().map(|thp| thp.page_size(trace.record.addr));
+		let tlb_hit = self.tlb.as_mut().map(|tlb| {
+			let page_size = page_size.unwrap_or_else(|| tlb.page_size());
+			tlb.access(trace.record.addr, page_size)
+		});
+		let page_walk_latency = match (&self.tlb, tlb_hit) {
+			(Some(tlb), Some(false)) => tlb.page_walk_latency(),
+			_ => FemtoDuration::ZERO,
+		};
+
+		// Calculate the latency of the access.
+		// Note: The access happens before any migrations, so it's served by the previous
+		//       memory. If the page was just mapped, we also pay the fault latency, and if
+		//       it was swapped in, we pay the fault latency of swap (a major fault).
+		//       Any tlb miss adds the page walk latency, and any split or collapse it's latency.
+		let access_mem = match (page_prev_mem_idx, page_swapped_in) {
+			(_, true) => statistics::AccessMem::SwappedIn(page_cur_mem_idx),
+			(Some(mem_idx), false) => statistics::AccessMem::Resided(mem_idx),
+			(None, _) => statistics::AccessMem::Mapped(page_cur_mem_idx),
+		};
+		let (latency, queueing_delay) = {
+			let (statistics::AccessMem::Mapped(mem_idx) |
+			statistics::AccessMem::Resided(mem_idx) |
+			statistics::AccessMem::SwappedIn(mem_idx)) = access_mem;
+			let mem = self.memories.get_mut(mem_idx);
+			let latencies = mem.latencies();
+			let (access_latency, traffic_kind) = match trace.record.kind {
+				pin_trace::RecordAccessKind::Read => (latencies.read, memories::TrafficKind::Read),
+				pin_trace::RecordAccessKind::Write => (latencies.write, memories::TrafficKind::Write),
+			};
+			let queueing_delay = mem.register_traffic(
+				trace.record.time,
+				self.config.bandwidth_window,
+				traffic_kind,
+				memories::ACCESS_BYTES,
+			);
+			let access_latency = access_latency + queueing_delay + page_walk_latency + thp_latency;
+
+			let latency = match access_mem {
+				statistics::AccessMem::Mapped(_) => access_latency + latencies.fault,
+				statistics::AccessMem::Resided(_) => access_latency,
+				statistics::AccessMem::SwappedIn(_) => {
+					let swap_mem_idx = swap_mem_idx.expect("Page was swapped in without swap");
+					access_latency + self.memories.get(swap_mem_idx).latencies().fault
+				},
+			};
+			(latency, queueing_delay)
+		};
+
 		// Finally register the access in our statistics
-		self.statistics.register_access(statistics::Access {
+		self.register_access(statistics::Access {
 			time: trace.record.time,
 			page_ptr,
 			kind: match trace.record.kind {
 				pin_trace::RecordAccessKind::Read => statistics::AccessKind::Read,
 				pin_trace::RecordAccessKind::Write => statistics::AccessKind::Write,
 			},
-			mem: match page_prev_mem_idx {
-				Some(mem_idx) => statistics::AccessMem::Resided(mem_idx),
-				None => statistics::AccessMem::Mapped(page_cur_mem_idx),
-			},
+			mem: access_mem,
 			prev_temperature: page_prev_temperature,
 			cur_temperature: page_cur_temperature,
 			caused_cooling,
+			latency,
+			queueing_delay,
+			tlb_hit,
 		});
 
 		Ok(())
 	}
 
+	fn finish(&mut self) {
+		self.statistics.finish();
+		self.observers.notify_finished_epochs(&self.statistics);
+	}
+
+	fn add_observer(&mut self, observer: impl Observer + 'static) {
+		self.observers.add(observer);
+	}
+
 	fn fmt_debug(&mut self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
 		// Note: Start with a newline, since we're a multi-line output
 		f.pad("\n")?;
 
+		let swap_mem_idx = self.memories.swap_memory();
 		for (mem_idx, memory) in self.memories.iter_mut() {
 			let name = memory.name();
 			let len = memory.page_len();
+
+			// Note: Swap has unlimited capacity, so there's no point in showing it
+			if Some(mem_idx) == swap_mem_idx {
+				writeln!(f, "Memory {name} ({mem_idx:?}): {len}")?;
+				continue;
+			}
+
 			let capacity = memory.page_capacity();
 			let occupancy_percentage = 100.0 * (len as f64 / capacity as f64);
 			writeln!(
@@ -313,6 +621,23 @@ impl sim::Classifier for HeMem {
 
 		Ok(())
 	}
+
+	fn debug_stats(&mut self) -> serde_json::Value {
+		let memories = self
+			.memories
+			.iter_mut()
+			.map(|(mem_idx, memory)| {
+				serde_json::json!({
+					"idx": mem_idx.to_usize(),
+					"name": memory.name(),
+					"page_len": memory.page_len(),
+					"page_capacity": memory.page_capacity(),
+				})
+			})
+			.collect::<Vec<_>>();
+
+		serde_json::json!({ "memories": memories })
+	}
 }
 
 /// Configuration
@@ -322,6 +647,45 @@ pub struct Config {
 	pub read_hot_threshold:  usize,
 	pub write_hot_threshold: usize,
 
-	/// Max threshold for global cooling
+	/// Max threshold for global cooling.
+	///
+	/// Ignored if the hotness estimator decays on it's own.
 	pub global_cooling_threshold: usize,
+
+	/// Hotness estimator each page starts with
+	pub hotness: Hotness,
+
+	/// Weights of the accesses of each page in it's temperature
+	pub temperature_weights: TemperatureWeights,
+
+	/// Order in which cold pages are selected to be cooled or evicted
+	pub cold_page_selection: ColdPageSelection,
+
+	/// Pages demoted at a time when cooling a memory
+	pub demotion_batch: usize,
+
+	/// Epoch length for the statistics, if any
+	pub epoch_length: Option<statistics::EpochLength>,
+
+	/// Whether to keep a log of all accesses in the statistics
+	pub access_log: bool,
+
+	/// Behavior when all memories are full
+	pub oom_behavior: OomBehavior,
+
+	/// Window over which bandwidth is accounted (in trace time)
+	pub bandwidth_window: u64,
+}
+
+/// Out-of-memory behavior
+#[derive(Clone, Copy, Debug)]
+pub enum OomBehavior {
+	/// Error out
+	Error,
+
+	/// Drop the access, counting it in the statistics
+	Drop,
+
+	/// Evict a page from the slowest memory to the swap memory
+	Swap,
 }
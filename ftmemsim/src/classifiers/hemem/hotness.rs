@@ -0,0 +1,322 @@
+//! Hotness estimators
+//!
+//! Estimate the recent read and write accesses of a page, which the thresholds
+//! and temperature are then based on.
+
+/// Hotness estimator of a page.
+///
+/// [`PageTable`](super::PageTable) is generic over it, so custom estimators may be used
+/// with it, while [`HeMem`](super::HeMem) uses [`Hotness`]. Each new page starts with a
+/// clone of an initial estimator.
+pub trait HotnessEstimator: Clone {
+	/// Registers a read access at `time`
+	fn register_read_access(&mut self, time: u64);
+
+	/// Registers a write access at `time`
+	fn register_write_access(&mut self, time: u64);
+
+	/// Returns the estimated read and write accesses at `time`
+	fn accesses(&self, time: u64) -> Accesses;
+
+	/// Cools the estimate `times` times, halving it each time
+	fn cool(&mut self, times: usize);
+
+	/// Returns if the estimate decays on it's own over time.
+	///
+	/// Such estimates don't need global coolings, so they never cause them.
+	fn decays(&self) -> bool {
+		false
+	}
+}
+
+/// Estimated accesses
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Accesses {
+	/// Reads
+	pub read: usize,
+
+	/// Writes
+	pub write: usize,
+}
+
+/// Hotness estimator, as any of the available estimators
+#[derive(Clone, Copy, Debug)]
+pub enum Hotness {
+	/// Halving clock
+	HalvingClock(HalvingClock),
+
+	/// Exponential moving average
+	Ema(Ema),
+
+	/// Sliding window
+	SlidingWindow(SlidingWindow),
+}
+
+impl HotnessEstimator for Hotness {
+	fn register_read_access(&mut self, time: u64) {
+		match self {
+			Self::HalvingClock(hotness) => hotness.register_read_access(time),
+			Self::Ema(hotness) => hotness.register_read_access(time),
+			Self::SlidingWindow(hotness) => hotness.register_read_access(time),
+		}
+	}
+
+	fn register_write_access(&mut self, time: u64) {
+		match self {
+			Self::HalvingClock(hotness) => hotness.register_write_access(time),
+			Self::Ema(hotness) => hotness.register_write_access(time),
+			Self::SlidingWindow(hotness) => hotness.register_write_access(time),
+		}
+	}
+
+	fn accesses(&self, time: u64) -> Accesses {
+		match self {
+			Self::HalvingClock(hotness) => hotness.accesses(time),
+			Self::Ema(hotness) => hotness.accesses(time),
+			Self::SlidingWindow(hotness) => hotness.accesses(time),
+		}
+	}
+
+	fn cool(&mut self, times: usize) {
+		match self {
+			Self::HalvingClock(hotness) => hotness.cool(times),
+			Self::Ema(hotness) => hotness.cool(times),
+			Self::SlidingWindow(hotness) => hotness.cool(times),
+		}
+	}
+
+	fn decays(&self) -> bool {
+		match self {
+			Self::HalvingClock(hotness) => hotness.decays(),
+			Self::Ema(hotness) => hotness.decays(),
+			Self::SlidingWindow(hotness) => hotness.decays(),
+		}
+	}
+}
+
+impl Default for Hotness {
+	fn default() -> Self {
+		Self::HalvingClock(HalvingClock::default())
+	}
+}
+
+/// Halving clock.
+///
+/// Counts all accesses, which only decay when cooled.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct HalvingClock {
+	/// Read accesses
+	read_accesses: usize,
+
+	/// Write accesses
+	write_accesses: usize,
+}
+
+impl HotnessEstimator for HalvingClock {
+	fn register_read_access(&mut self, _time: u64) {
+		self.read_accesses += 1;
+	}
+
+	fn register_write_access(&mut self, _time: u64) {
+		self.write_accesses += 1;
+	}
+
+	fn accesses(&self, _time: u64) -> Accesses {
+		Accesses {
+			read:  self.read_accesses,
+			write: self.write_accesses,
+		}
+	}
+
+	fn cool(&mut self, times: usize) {
+		let times = times.min(usize::BITS as usize - 1);
+		self.read_accesses >>= times;
+		self.write_accesses >>= times;
+	}
+}
+
+/// Exponential moving average over trace time.
+///
+/// Each access adds 1, and the average halves every half-life.
+#[derive(Clone, Copy, Debug)]
+pub struct Ema {
+	/// Half-life (in trace time)
+	half_life: u64,
+
+	/// Read accesses, as of `last_time`
+	read_accesses: f64,
+
+	/// Write accesses, as of `last_time`
+	write_accesses: f64,
+
+	/// Time of the last access
+	last_time: u64,
+}
+
+impl Ema {
+	/// Creates a new exponential moving average with half-life `half_life`
+	///
+	/// # Errors
+	/// Returns an error if the half-life is 0.
+	pub fn new(half_life: u64) -> Result<Self, anyhow::Error> {
+		anyhow::ensure!(half_life != 0, "Half-life must not be 0");
+
+		Ok(Self {
+			half_life,
+			read_accesses: 0.0,
+			write_accesses: 0.0,
+			last_time: 0,
+		})
+	}
+
+	/// Returns the decay factor from the last access until `time`
+	fn decay(&self, time: u64) -> f64 {
+		let elapsed = time.saturating_sub(self.last_time) as f64;
+		0.5_f64.powf(elapsed / self.half_life as f64)
+	}
+
+	/// Decays the accesses until `time`
+	fn decay_until(&mut self, time: u64) {
+		let decay = self.decay(time);
+		self.read_accesses *= decay;
+		self.write_accesses *= decay;
+		self.last_time = self.last_time.max(time);
+	}
+}
+
+impl HotnessEstimator for Ema {
+	fn register_read_access(&mut self, time: u64) {
+		self.decay_until(time);
+		self.read_accesses += 1.0;
+	}
+
+	fn register_write_access(&mut self, time: u64) {
+		self.decay_until(time);
+		self.write_accesses += 1.0;
+	}
+
+	fn accesses(&self, time: u64) -> Accesses {
+		// Note: We round to the nearest, so a page accessed just now counts as accessed
+		let decay = self.decay(time);
+		Accesses {
+			read:  (self.read_accesses * decay).round() as usize,
+			write: (self.write_accesses * decay).round() as usize,
+		}
+	}
+
+	fn cool(&mut self, times: usize) {
+		let factor = 0.5_f64.powi(times.min(i32::MAX as usize) as i32);
+		self.read_accesses *= factor;
+		self.write_accesses *= factor;
+	}
+
+	fn decays(&self) -> bool {
+		true
+	}
+}
+
+/// Sliding window.
+///
+/// Counts the accesses within the last window, split into [`SlidingWindow::BUCKETS`]
+/// buckets, so the count drops a bucket at a time.
+#[derive(Clone, Copy, Debug)]
+pub struct SlidingWindow {
+	/// Length of each bucket (in trace time)
+	bucket_len: u64,
+
+	/// Read and write accesses of each bucket
+	buckets: [(u32, u32); Self::BUCKETS],
+
+	/// Index of the latest bucket, since the start of the trace
+	cur_bucket: u64,
+}
+
+impl SlidingWindow {
+	/// Number of buckets
+	pub const BUCKETS: usize = 8;
+
+	/// Creates a new sliding window of length `window`
+	///
+	/// # Errors
+	/// Returns an error if the window is shorter than the number of buckets.
+	pub fn new(window: u64) -> Result<Self, anyhow::Error> {
+		anyhow::ensure!(
+			window >= Self::BUCKETS as u64,
+			"Window must be at least {} long",
+			Self::BUCKETS
+		);
+
+		Ok(Self {
+			bucket_len: window / Self::BUCKETS as u64,
+			buckets:    [(0, 0); Self::BUCKETS],
+			cur_bucket: 0,
+		})
+	}
+
+	/// Returns the bucket for an access at `time`, clearing any buckets that left the window
+	fn bucket_mut(&mut self, time: u64) -> &mut (u32, u32) {
+		let bucket = time / self.bucket_len;
+		if bucket > self.cur_bucket {
+			let cleared = (bucket - self.cur_bucket).min(Self::BUCKETS as u64);
+			for offset in 1..=cleared {
+				self.buckets[((self.cur_bucket + offset) % Self::BUCKETS as u64) as usize] = (0, 0);
+			}
+			self.cur_bucket = bucket;
+		}
+
+		&mut self.buckets[(self.cur_bucket % Self::BUCKETS as u64) as usize]
+	}
+}
+
+impl HotnessEstimator for SlidingWindow {
+	fn register_read_access(&mut self, time: u64) {
+		let (read_accesses, _) = self.bucket_mut(time);
+		*read_accesses = read_accesses.saturating_add(1);
+	}
+
+	fn register_write_access(&mut self, time: u64) {
+		let (_, write_accesses) = self.bucket_mut(time);
+		*write_accesses = write_accesses.saturating_add(1);
+	}
+
+	fn accesses(&self, time: u64) -> Accesses {
+		// Note: Only the buckets still within the window at `time` count
+		let elapsed_buckets = (time / self.bucket_len).saturating_sub(self.cur_bucket);
+		let live_buckets = (Self::BUCKETS as u64).saturating_sub(elapsed_buckets);
+		(0..live_buckets)
+			.map(|offset| {
+				self.buckets[((self.cur_bucket + Self::BUCKETS as u64 - offset) % Self::BUCKETS as u64) as usize]
+			})
+			.fold(Accesses::default(), |accesses, (read, write)| Accesses {
+				read:  accesses.read + read as usize,
+				write: accesses.write + write as usize,
+			})
+	}
+
+	fn cool(&mut self, times: usize) {
+		let times = times.min(u32::BITS as usize - 1);
+		for (read_accesses, write_accesses) in &mut self.buckets {
+			*read_accesses >>= times;
+			*write_accesses >>= times;
+		}
+	}
+
+	fn decays(&self) -> bool {
+		true
+	}
+}
+
+/// No estimate, for page tables that only keep track of the memory of each page.
+///
+/// All accesses are ignored, so pages are never hot.
+impl HotnessEstimator for () {
+	fn register_read_access(&mut self, _time: u64) {}
+
+	fn register_write_access(&mut self, _time: u64) {}
+
+	fn accesses(&self, _time: u64) -> Accesses {
+		Accesses::default()
+	}
+
+	fn cool(&mut self, _times: usize) {}
+}
@@ -1,28 +1,105 @@
 //! Memories
 
 // Imports
-use ftmemsim_util::FemtoDuration;
+use {anyhow::Context, ftmemsim_util::FemtoDuration};
+
+/// Bytes transferred by each access (a cache line)
+pub const ACCESS_BYTES: u64 = 64;
+
+/// Bytes transferred by each page migration
+pub const PAGE_BYTES: u64 = 4096;
 
 /// Memories.
 ///
-/// Maintains an array of memories, ordered from fastest to slowest.
+/// Maintains an array of memories, ordered from fastest to slowest,
+/// optionally followed by a swap memory.
+///
+/// Pages are promoted and demoted between memories according to each memory's
+/// migration targets, which default to the next faster and slower memory.
 #[derive(Clone, Debug)]
 pub struct Memories {
-	/// All memories
+	/// All memories, including the swap memory
 	memories: Vec<Memory>,
+
+	/// If the last memory is the swap memory
+	has_swap: bool,
+
+	/// Promotion targets of each memory, from nearest to furthest
+	promotion_targets: Vec<Vec<MemIdx>>,
+
+	/// Demotion targets of each memory, from nearest to furthest
+	demotion_targets: Vec<Vec<MemIdx>>,
 }
 
 impl Memories {
 	/// Creates all the memories from an iterator of memories.
 	///
 	/// Memories are expected to be ordered from fastest to slowest.
-	pub fn new(memories: impl IntoIterator<Item = Memory>) -> Self {
-		Self {
-			memories: memories.into_iter().collect(),
+	/// If `swap` is `Some`, it's added after the slowest memory.
+	///
+	/// # Errors
+	/// Returns an error if any migration targets are invalid, or if demotions may loop back to the same memory.
+	pub fn new(memories: impl IntoIterator<Item = Memory>, swap: Option<Memory>) -> Result<Self, anyhow::Error> {
+		let mut memories = memories.into_iter().collect::<Vec<_>>();
+		let regular_len = memories.len();
+
+		// Resolve the targets of each memory, defaulting to it's neighbors
+		let resolve_targets = |mem_idx: usize, targets: Option<&[Target]>, default_target: Option<usize>| match targets
+		{
+			Some(targets) => {
+				for target in targets {
+					anyhow::ensure!(
+						target.mem_idx < regular_len,
+						"Target memory {} doesn't exist",
+						target.mem_idx
+					);
+					anyhow::ensure!(target.mem_idx != mem_idx, "Memory can't target itself");
+				}
+
+				let mut targets = targets.to_vec();
+				targets.sort_by(|lhs, rhs| lhs.distance.total_cmp(&rhs.distance));
+				Ok(targets.into_iter().map(|target| MemIdx(target.mem_idx)).collect())
+			},
+			None => Ok(default_target.map(MemIdx).into_iter().collect()),
+		};
+		let mut promotion_targets = vec![];
+		let mut demotion_targets = vec![];
+		for (mem_idx, mem) in memories.iter().enumerate() {
+			let prev_mem_idx = mem_idx.checked_sub(1);
+			let next_mem_idx = Some(mem_idx + 1).filter(|&idx| idx < regular_len);
+			promotion_targets.push(
+				resolve_targets(mem_idx, mem.targets.promotion.as_deref(), prev_mem_idx)
+					.with_context(|| format!("Invalid promotion targets for memory {:?}", mem.name))?,
+			);
+			demotion_targets.push(
+				resolve_targets(mem_idx, mem.targets.demotion.as_deref(), next_mem_idx)
+					.with_context(|| format!("Invalid demotion targets for memory {:?}", mem.name))?,
+			);
 		}
+		self::ensure_acyclic(&demotion_targets).context("Demotion targets may not form a cycle")?;
+
+		// Note: The swap memory doesn't have any targets, pages are only ever swapped in or out of it
+		let has_swap = swap.is_some();
+		if has_swap {
+			promotion_targets.push(vec![]);
+			demotion_targets.push(vec![]);
+		}
+		memories.extend(swap);
+
+		Ok(Self {
+			memories,
+			has_swap,
+			promotion_targets,
+			demotion_targets,
+		})
 	}
 
-	/// Returns an iterator over all memories from fastest to slowest
+	/// Returns an iterator over all memories from fastest to slowest, including the swap memory
+	pub fn iter(&self) -> impl Iterator<Item = (MemIdx, &Memory)> {
+		self.memories.iter().enumerate().map(|(idx, mem)| (MemIdx(idx), mem))
+	}
+
+	/// Returns an iterator over all memories from fastest to slowest, including the swap memory
 	pub fn iter_mut(&mut self) -> impl Iterator<Item = (MemIdx, &mut Memory)> {
 		self.memories
 			.iter_mut()
@@ -30,6 +107,14 @@ impl Memories {
 			.map(|(idx, mem)| (MemIdx(idx), mem))
 	}
 
+	/// Returns a memory by it's memory index
+	///
+	/// # Panics
+	/// Panics if `idx` is an invalid memory index.
+	pub fn get(&self, idx: MemIdx) -> &Memory {
+		self.memories.get(idx.0).expect("Memory index was invalid")
+	}
+
 	/// Returns a memory by it's memory index
 	///
 	/// # Panics
@@ -65,21 +150,119 @@ impl Memories {
 		Ok(())
 	}
 
-	/// Returns the faster memory after `mem_idx`
-	pub fn faster_memory(&self, mem_idx: MemIdx) -> Option<MemIdx> {
-		match mem_idx.0 {
-			0 => None,
-			_ => Some(MemIdx(mem_idx.0 - 1)),
+	/// Returns the slowest memory, excluding the swap memory.
+	///
+	/// This is the last memory without any demotion targets, so pages may never be
+	/// demoted out of it other than to swap.
+	pub fn slowest_memory(&self) -> MemIdx {
+		// Note: Demotion targets are acyclic, so at least one memory has none.
+		(0..self.regular_len())
+			.rev()
+			.find(|&mem_idx| self.demotion_targets[mem_idx].is_empty())
+			.map(MemIdx)
+			.expect("Demotion targets were cyclic")
+	}
+
+	/// Returns the kind of migrating a page from `src` to `dst`.
+	///
+	/// Migrating to a memory reachable through the promotion targets of `src` is a
+	/// promotion, and through it's demotion targets is a demotion. Migrating out of
+	/// swap is a promotion, and into swap is a demotion.
+	///
+	/// Returns `None` if neither applies.
+	pub fn migration_kind(&self, src: MemIdx, dst: MemIdx) -> Option<MigrationKind> {
+		let swap_mem_idx = self.swap_memory();
+		if src == dst {
+			None
+		} else if Some(src) == swap_mem_idx || self::reaches(&self.promotion_targets, src, dst) {
+			Some(MigrationKind::Promotion)
+		} else if Some(dst) == swap_mem_idx || self::reaches(&self.demotion_targets, src, dst) {
+			Some(MigrationKind::Demotion)
+		} else {
+			None
+		}
+	}
+
+	/// Returns the swap memory, if any
+	pub fn swap_memory(&self) -> Option<MemIdx> {
+		match self.has_swap {
+			true => Some(MemIdx(self.memories.len() - 1)),
+			false => None,
+		}
+	}
+
+	/// Returns the memories to promote pages in `mem_idx` to, from nearest to furthest
+	///
+	/// # Panics
+	/// Panics if `mem_idx` is an invalid memory index.
+	pub fn promotion_targets(&self, mem_idx: MemIdx) -> &[MemIdx] {
+		self.promotion_targets.get(mem_idx.0).expect("Memory index was invalid")
+	}
+
+	/// Returns the memories to demote pages in `mem_idx` to, from nearest to furthest.
+	///
+	/// Pages are only moved to the swap memory under memory pressure, so it's never returned.
+	///
+	/// # Panics
+	/// Panics if `mem_idx` is an invalid memory index.
+	pub fn demotion_targets(&self, mem_idx: MemIdx) -> &[MemIdx] {
+		self.demotion_targets.get(mem_idx.0).expect("Memory index was invalid")
+	}
+
+	/// Returns the number of memories, excluding the swap memory
+	fn regular_len(&self) -> usize {
+		self.memories.len() - usize::from(self.has_swap)
+	}
+}
+
+/// Ensures that following `targets` never loops back to the same memory
+fn ensure_acyclic(targets: &[Vec<MemIdx>]) -> Result<(), anyhow::Error> {
+	/// Visit state of each memory
+	#[derive(Clone, Copy, PartialEq, Eq)]
+	enum State {
+		Unvisited,
+		Visiting,
+		Visited,
+	}
+
+	fn visit(targets: &[Vec<MemIdx>], states: &mut [State], mem_idx: usize) -> Result<(), anyhow::Error> {
+		match states[mem_idx] {
+			State::Unvisited => (),
+			State::Visiting => anyhow::bail!("Memory {mem_idx} is reachable from itself"),
+			State::Visited => return Ok(()),
+		}
+
+		states[mem_idx] = State::Visiting;
+		for target in &targets[mem_idx] {
+			visit(targets, states, target.0)?;
 		}
+		states[mem_idx] = State::Visited;
+
+		Ok(())
 	}
 
-	/// Returns the slower memory after `mem_idx`
-	pub fn slower_memory(&self, mem_idx: MemIdx) -> Option<MemIdx> {
-		match mem_idx.0 + 1 >= self.memories.len() {
-			true => None,
-			false => Some(MemIdx(mem_idx.0 + 1)),
+	let mut states = vec![State::Unvisited; targets.len()];
+	(0..targets.len()).try_for_each(|mem_idx| visit(targets, &mut states, mem_idx))
+}
+
+/// Returns if following `targets` from `src` reaches `dst`
+fn reaches(targets: &[Vec<MemIdx>], src: MemIdx, dst: MemIdx) -> bool {
+	let mut visited = vec![false; targets.len()];
+	let mut stack = vec![src];
+	while let Some(mem_idx) = stack.pop() {
+		for &target in &targets[mem_idx.0] {
+			if target == dst {
+				return true;
+			}
+
+			if !visited[target.0] {
+				visited[target.0] = true;
+				stack.push(target);
+			}
 		}
 	}
+
+	false
 }
 
 /// Memory index
@@ -104,17 +287,35 @@ pub struct Memory {
 	page_capacity: usize,
 
 	// Latencies
-	_latencies: AccessLatencies,
+	latencies: AccessLatencies,
+
+	/// Migration targets
+	targets: Targets,
+
+	/// Bandwidths
+	bandwidths: Bandwidths,
+
+	/// Traffic within the current bandwidth window
+	traffic: Traffic,
 }
 
 impl Memory {
 	/// Creates a new memory
-	pub fn new(name: impl Into<String>, page_capacity: usize, latencies: AccessLatencies) -> Self {
+	pub fn new(
+		name: impl Into<String>,
+		page_capacity: usize,
+		latencies: AccessLatencies,
+		targets: Targets,
+		bandwidths: Bandwidths,
+	) -> Self {
 		Self {
 			name: name.into(),
 			page_len: 0,
 			page_capacity,
-			_latencies: latencies,
+			latencies,
+			targets,
+			bandwidths,
+			traffic: Traffic::default(),
 		}
 	}
 
@@ -164,6 +365,89 @@ impl Memory {
 	pub fn page_capacity(&self) -> usize {
 		self.page_capacity
 	}
+
+	/// Returns the access latencies of this memory
+	pub fn latencies(&self) -> AccessLatencies {
+		self.latencies
+	}
+
+	/// Registers `bytes` of traffic at `time` on this memory and returns the queueing delay it incurs.
+	///
+	/// Traffic is accounted within windows of `window` (in trace time, which we assume to be nanoseconds).
+	/// Once the traffic within a window exceeds what the bandwidth allows, any further traffic is delayed
+	/// by the time it takes to transfer it's own share of the excess, so that the delays within a window
+	/// add up to the time it takes to transfer the whole excess.
+	pub fn register_traffic(&mut self, time: u64, window: u64, kind: TrafficKind, bytes: u64) -> FemtoDuration {
+		// Start a new window, if we're past the current one
+		let window_start = time - time % window;
+		if window_start != self.traffic.window_start {
+			self.traffic = Traffic {
+				window_start,
+				..Traffic::default()
+			};
+		}
+
+		let (bandwidth, window_bytes) = match kind {
+			TrafficKind::Read => (self.bandwidths.read, &mut self.traffic.read_bytes),
+			TrafficKind::Write => (self.bandwidths.write, &mut self.traffic.write_bytes),
+		};
+		*window_bytes += bytes;
+
+		// Note: Bandwidths are in GB/s, which are bytes per nanosecond
+		let Some(bandwidth) = bandwidth else {
+			return FemtoDuration::ZERO;
+		};
+		// Note: Only the bytes of this traffic past the bandwidth are delayed, since any previous
+		//       excess of the window was already charged to the traffic that caused it.
+		let excess_bytes = (*window_bytes as f64 - bandwidth * window as f64).min(bytes as f64);
+		match excess_bytes > 0.0 {
+			true => FemtoDuration::from_nanos_f64(excess_bytes / bandwidth),
+			false => FemtoDuration::ZERO,
+		}
+	}
+}
+
+/// Memory bandwidths (in GB/s)
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Bandwidths {
+	/// Read bandwidth, if limited
+	pub read: Option<f64>,
+
+	/// Write bandwidth, if limited
+	pub write: Option<f64>,
+}
+
+/// Page migration kind
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum MigrationKind {
+	/// Promotion, to a faster memory
+	Promotion,
+
+	/// Demotion, to a slower memory
+	Demotion,
+}
+
+/// Memory traffic kind
+#[derive(Clone, Copy, Debug)]
+pub enum TrafficKind {
+	/// Read
+	Read,
+
+	/// Write
+	Write,
+}
+
+/// Memory traffic within a bandwidth window
+#[derive(Clone, Copy, Default, Debug)]
+struct Traffic {
+	/// Window start time
+	window_start: u64,
+
+	/// Bytes read
+	read_bytes: u64,
+
+	/// Bytes written
+	write_bytes: u64,
 }
 
 /// Access latencies
@@ -178,3 +462,29 @@ pub struct AccessLatencies {
 	/// Fault latency
 	pub fault: FemtoDuration,
 }
+
+/// Migration targets of a memory
+#[derive(Clone, Default, Debug)]
+pub struct Targets {
+	/// Memories to promote pages to.
+	///
+	/// If `None`, defaults to the next faster memory.
+	pub promotion: Option<Vec<Target>>,
+
+	/// Memories to demote pages to.
+	///
+	/// If `None`, defaults to the next slower memory.
+	pub demotion: Option<Vec<Target>>,
+}
+
+/// Migration target
+#[derive(Clone, Copy, Debug)]
+pub struct Target {
+	/// Memory index
+	pub mem_idx: usize,
+
+	/// Distance to the memory.
+	///
+	/// Targets are tried from nearest to furthest.
+	pub distance: f64,
+}
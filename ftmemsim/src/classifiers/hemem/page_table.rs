@@ -2,45 +2,67 @@
 
 // Imports
 use {
-	super::memories::MemIdx,
-	std::collections::{btree_map, BTreeMap, BTreeSet},
+	super::{
+		hotness::{Hotness, HotnessEstimator},
+		memories::MemIdx,
+	},
+	itertools::Itertools,
+	std::{
+		collections::{hash_map, HashMap},
+		hash::{BuildHasherDefault, Hasher},
+		iter,
+	},
 };
 
-/// Page table
+/// Page table.
+///
+/// Stores all pages in a single array, with each memory's pages linked
+/// through it in the order they arrived at the memory.
+///
+/// Each page's hotness is estimated by a `H`.
 #[derive(Debug)]
-pub struct PageTable {
-	/// All pages, by their address
-	// TODO: `HashMap` with custom hash? We don't use the order
-	pages: BTreeMap<PagePtr, Page>,
+pub struct PageTable<H = Hotness> {
+	/// All pages
+	pages: Vec<Page<H>>,
 
-	/// Pages, by memory.
-	///
-	/// An index to be able to quickly find pages by their memory
-	pages_by_mem: BTreeMap<MemIdx, BTreeSet<PagePtr>>,
+	/// Index of each page in `pages`, by it's address
+	page_idxs: HashMap<PagePtr, usize, BuildHasherDefault<PagePtrHasher>>,
+
+	/// Pages of each memory, by memory index
+	mem_lists: Vec<MemList>,
 
 	/// Current cooling clock tick
 	cooling_clock_tick: usize,
 }
 
-impl PageTable {
+impl<H: HotnessEstimator> PageTable<H> {
 	/// Creates an empty page table
 	pub fn new() -> Self {
 		Self {
-			pages:              BTreeMap::new(),
-			pages_by_mem:       BTreeMap::new(),
+			pages:              vec![],
+			page_idxs:          HashMap::default(),
+			mem_lists:          vec![],
 			cooling_clock_tick: 0,
 		}
 	}
 
 	/// Returns if a page exists in this page table
 	pub fn contains(&self, page_ptr: PagePtr) -> bool {
-		self.pages.contains_key(&page_ptr)
+		self.page_idxs.contains_key(&page_ptr)
+	}
+
+	/// Returns the memory of a page, if it exists
+	pub fn mem_idx(&self, page_ptr: PagePtr) -> Option<MemIdx> {
+		self.page_idxs
+			.get(&page_ptr)
+			.map(|&page_idx| self.pages[page_idx].mem_idx)
 	}
 
 	/// Returns a page from this page table.
-	pub fn get_mut(&mut self, page_ptr: PagePtr) -> Option<&mut Page> {
+	pub fn get_mut(&mut self, page_ptr: PagePtr) -> Option<&mut Page<H>> {
 		// Try to get the page
-		let page = self.pages.get_mut(&page_ptr)?;
+		let page_idx = *self.page_idxs.get(&page_ptr)?;
+		let page = &mut self.pages[page_idx];
 
 		// Then cool it before returning
 		page.cool_accesses(self.cooling_clock_tick);
@@ -52,13 +74,14 @@ impl PageTable {
 	/// # Panics
 	/// Panics if `page_ptr` is an invalid page pointer
 	pub fn move_mem(&mut self, page_ptr: PagePtr, mem_idx: MemIdx) {
-		let page = self.pages.get_mut(&page_ptr).expect("Invalid page pointer");
+		let page_idx = *self.page_idxs.get(&page_ptr).expect("Invalid page pointer");
+		let page = &mut self.pages[page_idx];
 		page.cool_accesses(self.cooling_clock_tick);
 
 		if mem_idx != page.mem_idx {
-			self.pages_by_mem.entry(page.mem_idx).or_default().remove(&page_ptr);
-			self.pages_by_mem.entry(mem_idx).or_default().insert(page_ptr);
-			page.mem_idx = mem_idx;
+			self.unlink(page_idx);
+			self.pages[page_idx].mem_idx = mem_idx;
+			self.link_back(page_idx);
 		}
 	}
 
@@ -66,18 +89,19 @@ impl PageTable {
 	///
 	/// # Errors
 	/// Returns an error if the page already exists
-	pub fn insert(&mut self, mut page: Page) -> Result<(), anyhow::Error> {
-		match self.pages.entry(page.ptr) {
-			btree_map::Entry::Vacant(entry) => {
+	pub fn insert(&mut self, mut page: Page<H>) -> Result<(), anyhow::Error> {
+		match self.page_idxs.entry(page.ptr) {
+			hash_map::Entry::Vacant(entry) => {
 				// Note: We cool it before inserting to ensure that the page is up to date.
 				page.cool_accesses(self.cooling_clock_tick);
-				self.pages_by_mem.entry(page.mem_idx).or_default().insert(page.ptr);
-				entry.insert(page);
-
+				let page_idx = self.pages.len();
+				self.pages.push(page);
+				entry.insert(page_idx);
+				self.link_back(page_idx);
 
 				Ok(())
 			},
-			btree_map::Entry::Occupied(_) => anyhow::bail!("Page already existed: {page:?}"),
+			hash_map::Entry::Occupied(_) => anyhow::bail!("Page already existed: {:?}", page.ptr),
 		}
 	}
 
@@ -89,62 +113,161 @@ impl PageTable {
 		self.cooling_clock_tick += 1;
 	}
 
-	/// Returns `count` cold pages  in memory `mem_idx`.
+	/// Returns all pages in memory `mem_idx`, in the order they arrived at it
+	pub fn mem_pages(&self, mem_idx: MemIdx) -> impl Iterator<Item = PagePtr> + '_ {
+		self.mem_list_pages(mem_idx).map(|page| page.ptr)
+	}
+
+	/// Returns (at most) `count` cold pages in memory `mem_idx` at `time`, selected with `options`.
 	///
-	/// The chosen memories aren't necessarily the coldest, they are just
-	/// guaranteed to be cold
-	pub fn cold_pages(
-		&mut self,
-		read_hot_threshold: usize,
-		write_hot_threshold: usize,
-		mem_idx: MemIdx,
-	) -> impl Iterator<Item = PagePtr> + '_ {
-		let pages = &self.pages;
-		self.pages_by_mem
-			.entry(mem_idx)
-			.or_default()
-			.iter()
-			.copied()
-			.filter(move |page_ptr| {
-				!pages
-					.get(page_ptr)
-					.expect("Invalid page pointer")
-					.is_hot(read_hot_threshold, write_hot_threshold)
-			})
+	/// When selecting by address, the lowest addresses are found with a single scan of the memory's pages.
+	pub fn cold_pages(&self, time: u64, mem_idx: MemIdx, options: ColdPageOptions, count: usize) -> Vec<PagePtr> {
+		// Note: We cool a copy of the pages first, else pages that haven't
+		//       been accessed since the last coolings would seem hotter.
+		let cooled = |page: &Page<H>| {
+			let mut page = page.clone();
+			page.cool_accesses(self.cooling_clock_tick);
+			page
+		};
+		let is_cold = |page: &Page<H>| !page.is_hot(time, options.read_hot_threshold, options.write_hot_threshold);
+		let cold_pages = self.mem_list_pages(mem_idx).map(cooled).filter(is_cold);
+
+		match options.selection {
+			ColdPageSelection::Address => cold_pages.map(|page| page.ptr).k_smallest(count).collect(),
+			ColdPageSelection::Coldest => cold_pages
+				.map(|page| (page.temperature(time, options.weights), page.ptr))
+				.k_smallest(count)
+				.map(|(_, page_ptr)| page_ptr)
+				.collect(),
+			ColdPageSelection::Lru => cold_pages
+				.map(|page| (page.last_access_time, page.ptr))
+				.k_smallest(count)
+				.map(|(_, page_ptr)| page_ptr)
+				.collect(),
+		}
+	}
+
+	/// Returns all pages in the list of memory `mem_idx`
+	fn mem_list_pages(&self, mem_idx: MemIdx) -> impl Iterator<Item = &Page<H>> + '_ {
+		let head = self
+			.mem_lists
+			.get(mem_idx.to_usize())
+			.and_then(|mem_list| mem_list.head);
+		iter::successors(head.map(|page_idx| &self.pages[page_idx]), |page| {
+			page.next.map(|page_idx| &self.pages[page_idx])
+		})
+	}
+
+	/// Links page `page_idx` at the back of it's memory's list
+	fn link_back(&mut self, page_idx: usize) {
+		let mem_idx = self.pages[page_idx].mem_idx.to_usize();
+		if mem_idx >= self.mem_lists.len() {
+			self.mem_lists.resize_with(mem_idx + 1, MemList::default);
+		}
+
+		let mem_list = &mut self.mem_lists[mem_idx];
+		match mem_list.tail {
+			Some(tail_idx) => self.pages[tail_idx].next = Some(page_idx),
+			None => mem_list.head = Some(page_idx),
+		}
+		let page = &mut self.pages[page_idx];
+		page.prev = mem_list.tail;
+		page.next = None;
+		mem_list.tail = Some(page_idx);
+	}
+
+	/// Unlinks page `page_idx` from it's memory's list
+	fn unlink(&mut self, page_idx: usize) {
+		let Page {
+			prev, next, mem_idx, ..
+		} = self.pages[page_idx];
+		let mem_list = &mut self.mem_lists[mem_idx.to_usize()];
+		match prev {
+			Some(prev_idx) => self.pages[prev_idx].next = next,
+			None => mem_list.head = next,
+		}
+		match next {
+			Some(next_idx) => self.pages[next_idx].prev = prev,
+			None => mem_list.tail = prev,
+		}
 	}
 }
 
-impl Default for PageTable {
+impl<H: HotnessEstimator> Default for PageTable<H> {
 	fn default() -> Self {
 		Self::new()
 	}
 }
 
+/// List of the pages of a memory
+#[derive(Clone, Default, Debug)]
+struct MemList {
+	/// Index of the first page
+	head: Option<usize>,
+
+	/// Index of the last page
+	tail: Option<usize>,
+}
+
+/// Page pointer hasher.
+///
+/// Page pointers are just integers, so we hash them with a single multiplication,
+/// instead of the (slower) default hasher.
+#[derive(Clone, Copy, Default, Debug)]
+struct PagePtrHasher(u64);
+
+impl Hasher for PagePtrHasher {
+	fn finish(&self) -> u64 {
+		// Note: The multiplication leaves the low bits poorly mixed, so we fold the high bits into them
+		self.0 ^ (self.0 >> 32)
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		for &byte in bytes {
+			self.write_u64(u64::from(byte));
+		}
+	}
+
+	fn write_u64(&mut self, value: u64) {
+		self.0 = (self.0 ^ value).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+	}
+}
+
 /// Page
 #[derive(Clone, Copy, Debug)]
-pub struct Page {
+pub struct Page<H = Hotness> {
 	/// Pointer
 	ptr: PagePtr,
 
 	/// Memory index
 	mem_idx: MemIdx,
 
-	// Read/Write accesses (adjusted)
-	adjusted_read_accesses:  usize,
-	adjusted_write_accesses: usize,
+	/// Hotness estimate
+	hotness: H,
+
+	/// Time of the last access
+	last_access_time: u64,
+
+	/// Index of the previous page in this page's memory
+	prev: Option<usize>,
+
+	/// Index of the next page in this page's memory
+	next: Option<usize>,
 
 	// Current cooling clock tick
 	cur_cooling_clock_tick: usize,
 }
 
-impl Page {
-	/// Creates a new page
-	pub fn new(ptr: PagePtr, mem_idx: MemIdx) -> Self {
+impl<H: HotnessEstimator> Page<H> {
+	/// Creates a new page, estimating it's hotness with `hotness`
+	pub fn new(ptr: PagePtr, mem_idx: MemIdx, hotness: H) -> Self {
 		Self {
 			ptr,
 			mem_idx,
-			adjusted_read_accesses: 0,
-			adjusted_write_accesses: 0,
+			hotness,
+			last_access_time: 0,
+			prev: None,
+			next: None,
 			cur_cooling_clock_tick: 0,
 		}
 	}
@@ -154,43 +277,92 @@ impl Page {
 		self.mem_idx
 	}
 
-	/// Registers a read access
-	pub fn register_read_access(&mut self) {
-		self.adjusted_read_accesses += 1;
+	/// Registers a read access at `time`
+	pub fn register_read_access(&mut self, time: u64) {
+		self.hotness.register_read_access(time);
+		self.last_access_time = time;
 	}
 
-	/// Registers a write access
-	pub fn register_write_access(&mut self) {
-		self.adjusted_write_accesses += 1;
+	/// Registers a write access at `time`
+	pub fn register_write_access(&mut self, time: u64) {
+		self.hotness.register_write_access(time);
+		self.last_access_time = time;
 	}
 
-	/// Returns if this page is hot
-	pub fn is_hot(&self, read_hot_threshold: usize, write_hot_threshold: usize) -> bool {
-		self.adjusted_read_accesses >= read_hot_threshold || self.adjusted_write_accesses >= write_hot_threshold
+	/// Returns if this page is hot at `time`
+	pub fn is_hot(&self, time: u64, read_hot_threshold: usize, write_hot_threshold: usize) -> bool {
+		let accesses = self.hotness.accesses(time);
+		accesses.read >= read_hot_threshold || accesses.write >= write_hot_threshold
 	}
 
-	/// Returns this page's temperature
-	pub fn temperature(&self) -> usize {
-		// TODO: Tune this definition?
-		self.adjusted_read_accesses + self.adjusted_write_accesses * 2
+	/// Returns this page's temperature at `time`, weighing it's accesses by `weights`
+	pub fn temperature(&self, time: u64, weights: TemperatureWeights) -> usize {
+		let accesses = self.hotness.accesses(time);
+		(accesses.read as f64 * weights.read + accesses.write as f64 * weights.write).round() as usize
 	}
 
-	/// Returns if either read or write accesses are over a threshold
-	pub fn over_threshold(&self, threshold: usize) -> bool {
-		self.adjusted_read_accesses >= threshold || self.adjusted_write_accesses >= threshold
+	/// Returns if either read or write accesses are over a threshold at `time`
+	pub fn over_threshold(&self, time: u64, threshold: usize) -> bool {
+		let accesses = self.hotness.accesses(time);
+		accesses.read >= threshold || accesses.write >= threshold
 	}
 
 	/// Cools this page's accesses to match the global cooling clock
 	fn cool_accesses(&mut self, global_access_cooling_clock_tick: usize) {
 		assert!(self.cur_cooling_clock_tick <= global_access_cooling_clock_tick);
 
-		let offset = (global_access_cooling_clock_tick - self.cur_cooling_clock_tick).min(usize::BITS as usize - 1);
-		self.adjusted_read_accesses >>= offset;
-		self.adjusted_write_accesses >>= offset;
+		self.hotness
+			.cool(global_access_cooling_clock_tick - self.cur_cooling_clock_tick);
 		self.cur_cooling_clock_tick = global_access_cooling_clock_tick;
 	}
 }
 
+/// Weights of the accesses of a page in it's temperature
+#[derive(Clone, Copy, Debug)]
+pub struct TemperatureWeights {
+	/// Read accesses weight
+	pub read: f64,
+
+	/// Write accesses weight
+	pub write: f64,
+}
+
+impl Default for TemperatureWeights {
+	fn default() -> Self {
+		Self { read: 1.0, write: 2.0 }
+	}
+}
+
+/// Options for selecting cold pages
+#[derive(Clone, Copy, Debug)]
+pub struct ColdPageOptions {
+	/// Read accesses for a page to be hot
+	pub read_hot_threshold: usize,
+
+	/// Write accesses for a page to be hot
+	pub write_hot_threshold: usize,
+
+	/// Order in which the pages are selected
+	pub selection: ColdPageSelection,
+
+	/// Weights of the temperatures, only used when selecting the coldest pages
+	pub weights: TemperatureWeights,
+}
+
+/// Order in which cold pages are selected
+#[derive(Clone, Copy, Default, Debug)]
+pub enum ColdPageSelection {
+	/// Lowest address first
+	#[default]
+	Address,
+
+	/// Lowest temperature first
+	Coldest,
+
+	/// Least recently accessed first
+	Lru,
+}
+
 /// Page pointer.
 ///
 /// Guaranteed to be page-aligned
@@ -221,3 +393,177 @@ impl PagePtr {
 		self.0
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		crate::classifiers::hemem::{memories, Memories, Memory},
+		ftmemsim_util::FemtoDuration,
+	};
+
+	/// Returns the indexes of 2 memories
+	fn mem_idxs() -> [MemIdx; 2] {
+		let memory = |name| {
+			Memory::new(
+				name,
+				usize::MAX,
+				memories::AccessLatencies {
+					read:  FemtoDuration::ZERO,
+					write: FemtoDuration::ZERO,
+					fault: FemtoDuration::ZERO,
+				},
+				memories::Targets::default(),
+				memories::Bandwidths::default(),
+			)
+		};
+		let memories = Memories::new([memory("fast"), memory("slow")], None).expect("Unable to create memories");
+		let mut mem_idxs = memories.iter().map(|(mem_idx, _)| mem_idx);
+		[mem_idxs.next().unwrap(), mem_idxs.next().unwrap()]
+	}
+
+	/// Returns the page pointer of page `idx`
+	fn page_ptr(idx: u64) -> PagePtr {
+		PagePtr::new(idx * 0x1000)
+	}
+
+	/// Returns a page table with pages `idxs` inserted, in order, into `mem_idx`
+	fn page_table(mem_idx: MemIdx, idxs: &[u64]) -> PageTable {
+		let mut page_table = PageTable::new();
+		for &idx in idxs {
+			page_table
+				.insert(Page::new(self::page_ptr(idx), mem_idx, Hotness::default()))
+				.expect("Unable to insert page");
+		}
+
+		page_table
+	}
+
+	/// Asserts that memory `mem_idx` has pages `idxs`, in arrival order.
+	///
+	/// Checks the list both forwards and backwards.
+	fn assert_mem_pages(page_table: &PageTable, mem_idx: MemIdx, idxs: &[u64]) {
+		let expected = idxs.iter().map(|&idx| self::page_ptr(idx)).collect::<Vec<_>>();
+		assert_eq!(page_table.mem_pages(mem_idx).collect::<Vec<_>>(), expected);
+
+		let tail = page_table
+			.mem_lists
+			.get(mem_idx.to_usize())
+			.and_then(|mem_list| mem_list.tail);
+		let mut backwards = iter::successors(tail.map(|page_idx| &page_table.pages[page_idx]), |page| {
+			page.prev.map(|page_idx| &page_table.pages[page_idx])
+		})
+		.map(|page| page.ptr)
+		.collect::<Vec<_>>();
+		backwards.reverse();
+		assert_eq!(backwards, expected);
+	}
+
+	#[test]
+	fn insert() {
+		let [fast, slow] = self::mem_idxs();
+		let mut page_table = self::page_table(fast, &[3, 1, 2]);
+		page_table
+			.insert(Page::new(self::page_ptr(0), slow, Hotness::default()))
+			.expect("Unable to insert page");
+
+		self::assert_mem_pages(&page_table, fast, &[3, 1, 2]);
+		self::assert_mem_pages(&page_table, slow, &[0]);
+		assert!(page_table
+			.insert(Page::new(self::page_ptr(1), slow, Hotness::default()))
+			.is_err());
+	}
+
+	#[test]
+	fn move_mem() {
+		let [fast, slow] = self::mem_idxs();
+		let mut page_table = self::page_table(fast, &[0, 1, 2]);
+
+		page_table.move_mem(self::page_ptr(1), slow);
+		page_table.move_mem(self::page_ptr(0), slow);
+		self::assert_mem_pages(&page_table, fast, &[2]);
+		self::assert_mem_pages(&page_table, slow, &[1, 0]);
+
+		// Note: Moving to the same memory keeps it's position
+		page_table.move_mem(self::page_ptr(1), slow);
+		self::assert_mem_pages(&page_table, slow, &[1, 0]);
+
+		page_table.move_mem(self::page_ptr(1), fast);
+		self::assert_mem_pages(&page_table, fast, &[2, 1]);
+		self::assert_mem_pages(&page_table, slow, &[0]);
+	}
+
+	#[test]
+	fn unlink_head_middle_tail() {
+		let [fast, slow] = self::mem_idxs();
+		for (removed, remaining) in [(0, [1, 2, 3]), (2, [0, 1, 3]), (3, [0, 1, 2])] {
+			let mut page_table = self::page_table(fast, &[0, 1, 2, 3]);
+			page_table.move_mem(self::page_ptr(removed), slow);
+			self::assert_mem_pages(&page_table, fast, &remaining);
+			self::assert_mem_pages(&page_table, slow, &[removed]);
+
+			// Then ensure the list is still appended to correctly
+			page_table.move_mem(self::page_ptr(removed), fast);
+			let mut idxs = remaining.to_vec();
+			idxs.push(removed);
+			self::assert_mem_pages(&page_table, fast, &idxs);
+			self::assert_mem_pages(&page_table, slow, &[]);
+		}
+	}
+
+	#[test]
+	fn unlink_all() {
+		let [fast, slow] = self::mem_idxs();
+		let mut page_table = self::page_table(fast, &[0, 1]);
+		page_table.move_mem(self::page_ptr(1), slow);
+		page_table.move_mem(self::page_ptr(0), slow);
+		self::assert_mem_pages(&page_table, fast, &[]);
+
+		page_table.move_mem(self::page_ptr(0), fast);
+		self::assert_mem_pages(&page_table, fast, &[0]);
+	}
+
+	#[test]
+	fn cold_pages_by_address() {
+		let [fast, _] = self::mem_idxs();
+		let mut page_table = self::page_table(fast, &[4, 2, 0, 3, 1]);
+		for _ in 0..2 {
+			page_table
+				.get_mut(self::page_ptr(0))
+				.expect("Page wasn't in page table")
+				.register_read_access(0);
+		}
+
+		let options = ColdPageOptions {
+			read_hot_threshold:  2,
+			write_hot_threshold: 2,
+			selection:           ColdPageSelection::Address,
+			weights:             TemperatureWeights::default(),
+		};
+		let cold_pages = page_table.cold_pages(0, fast, options, 3);
+		assert_eq!(cold_pages, [1, 2, 3].map(self::page_ptr));
+	}
+
+	#[test]
+	fn cold_pages_cooled() {
+		let [fast, _] = self::mem_idxs();
+		let mut page_table = self::page_table(fast, &[0]);
+		for _ in 0..2 {
+			page_table
+				.get_mut(self::page_ptr(0))
+				.expect("Page wasn't in page table")
+				.register_read_access(0);
+		}
+
+		// Note: The page was hot, but is cold once cooled, even if it isn't accessed again
+		let options = ColdPageOptions {
+			read_hot_threshold:  2,
+			write_hot_threshold: 2,
+			selection:           ColdPageSelection::Coldest,
+			weights:             TemperatureWeights::default(),
+		};
+		assert_eq!(page_table.cold_pages(0, fast, options, 1), []);
+		page_table.cool_all_pages();
+		assert_eq!(page_table.cold_pages(0, fast, options, 1), [self::page_ptr(0)]);
+	}
+}
@@ -2,53 +2,331 @@
 
 // Imports
 use {
-	super::{memories::MemIdx, PagePtr},
+	super::{
+		memories::{self, MemIdx, MigrationKind},
+		PagePtr,
+	},
+	ftmemsim_util::FemtoDuration,
 	std::collections::HashMap,
 };
 
 /// Statistics
 #[derive(Clone, Debug)]
 pub struct Statistics {
-	/// All accesses
-	accesses: Vec<Access>,
+	/// All accesses, if we're keeping an access log
+	accesses: Option<Vec<Access>>,
 
 	/// Page migrations
 	page_migration: HashMap<PagePtr, Vec<PageMigration>>,
+
+	/// Epoch length
+	epoch_length: Option<EpochLength>,
+
+	/// All finished epochs
+	epochs: Vec<Epoch>,
+
+	/// Current epoch
+	cur_epoch: Option<Epoch>,
+
+	/// Totals of the whole run, as a single epoch
+	total: Option<Epoch>,
+
+	/// Current occupancy of each memory.
+	///
+	/// Tracked through the page migrations, so we may snapshot it at the end of each epoch.
+	occupancies: Vec<usize>,
+
+	/// Current number of pages of each generation of each memory, for classifiers with generations.
+	///
+	/// Snapshot at the end of each epoch, like the occupancies.
+	generation_sizes: Vec<Vec<usize>>,
+
+	/// Writes to each page, by memory.
+	///
+	/// Includes the writes of migrating a page into the memory.
+	page_writes: Vec<HashMap<PagePtr, u64>>,
+
+	/// Transparent huge page splits and collapses
+	thp_events: Vec<ThpEvent>,
+
+	/// Hot threshold changes, for classifiers that adapt it
+	hot_thresholds: Vec<HotThreshold>,
 }
 
 impl Statistics {
-	/// Creates new, empty, statistics
-	pub fn new() -> Self {
+	/// Creates new, empty, statistics.
+	///
+	/// If `epoch_length` is `Some`, also keeps per-epoch summaries.
+	/// If `access_log` is `true`, also keeps a log of all accesses.
+	pub fn new(epoch_length: Option<EpochLength>, access_log: bool) -> Self {
 		Self {
-			accesses:       vec![],
+			accesses: access_log.then(Vec::new),
 			page_migration: HashMap::new(),
+			epoch_length,
+			epochs: vec![],
+			cur_epoch: None,
+			total: None,
+			occupancies: vec![],
+			generation_sizes: vec![],
+			page_writes: vec![],
+			thp_events: vec![],
+			hot_thresholds: vec![],
 		}
 	}
 
 	/// Registers an access on these statistics
 	pub fn register_access(&mut self, access: Access) {
-		self.accesses.push(access);
+		self.total
+			.get_or_insert_with(|| Epoch::new(access.time))
+			.register_access(&access);
+
+		if let Some(epoch) = self.epoch_at(access.time) {
+			epoch.register_access(&access);
+		}
+
+		if let AccessKind::Write = access.kind {
+			let (AccessMem::Mapped(mem_idx) | AccessMem::Resided(mem_idx) | AccessMem::SwappedIn(mem_idx)) = access.mem;
+			*self.page_writes_mut(mem_idx).entry(access.page_ptr).or_default() += 1;
+		}
+
+		if let Some(accesses) = &mut self.accesses {
+			accesses.push(access);
+		}
+	}
+
+	/// Registers a trace record, whether or not it's registered as an access.
+	///
+	/// Epochs measured in records count these, so they follow the trace even if
+	/// some records are dropped, or filtered before reaching the classifier.
+	pub fn register_trace_record(&mut self, time: u64) {
+		self.total
+			.get_or_insert_with(|| Epoch::new(time))
+			.register_trace_record(time);
+
+		// If we're counting records, finish the current epoch once it's full
+		if let (Some(EpochLength::Records(records)), Some(epoch)) = (self.epoch_length, &self.cur_epoch) {
+			if epoch.trace_records >= records {
+				self.finish_epoch();
+			}
+		}
+
+		if let Some(epoch) = self.epoch_at(time) {
+			epoch.register_trace_record(time);
+		}
+	}
+
+	/// Finishes the current epoch, if any, once the run is finished
+	pub fn finish(&mut self) {
+		self.finish_epoch();
 	}
 
 	/// Registers migration for a page
 	pub fn register_page_migration(&mut self, page_ptr: PagePtr, page_migration: PageMigration) {
+		if let Some(prev_mem_idx) = page_migration.prev_mem_idx {
+			*self.occupancy_mut(prev_mem_idx) -= 1;
+
+			// Note: Migrating a page writes all of it to the new memory
+			*self
+				.page_writes_mut(page_migration.cur_mem_idx)
+				.entry(page_ptr)
+				.or_default() += 1;
+		}
+		*self.occupancy_mut(page_migration.cur_mem_idx) += 1;
+
+		self.total
+			.get_or_insert_with(|| Epoch::new(page_migration.time))
+			.register_page_migration(&page_migration);
+		if let Some(epoch) = self.epoch_at(page_migration.time) {
+			epoch.register_page_migration(&page_migration);
+		}
+
 		self.page_migration.entry(page_ptr).or_default().push(page_migration);
 	}
 
-	/// Returns all accesses
-	pub fn accesses(&self) -> &[Access] {
-		&self.accesses
+	/// Registers an access that was dropped due to all memories being full
+	pub fn register_dropped_access(&mut self, time: u64) {
+		self.total
+			.get_or_insert_with(|| Epoch::new(time))
+			.register_dropped_access(time);
+		if let Some(epoch) = self.epoch_at(time) {
+			epoch.register_dropped_access(time);
+		}
+	}
+
+	/// Registers a transparent huge page split or collapse
+	pub fn register_thp_event(&mut self, thp_event: ThpEvent) {
+		self.total
+			.get_or_insert_with(|| Epoch::new(thp_event.time))
+			.register_thp_event(&thp_event);
+		if let Some(epoch) = self.epoch_at(thp_event.time) {
+			epoch.register_thp_event(&thp_event);
+		}
+
+		self.thp_events.push(thp_event);
+	}
+
+	/// Sets the number of pages of each generation of memory `mem_idx`
+	pub fn set_generation_sizes(&mut self, mem_idx: MemIdx, generation_sizes: Vec<usize>) {
+		let mem_idx = mem_idx.to_usize();
+		if mem_idx >= self.generation_sizes.len() {
+			self.generation_sizes.resize_with(mem_idx + 1, Vec::new);
+		}
+
+		self.generation_sizes[mem_idx] = generation_sizes;
+	}
+
+	/// Registers a change of the hot threshold
+	pub fn register_hot_threshold(&mut self, hot_threshold: HotThreshold) {
+		self.total
+			.get_or_insert_with(|| Epoch::new(hot_threshold.time))
+			.register_hot_threshold(&hot_threshold);
+		if let Some(epoch) = self.epoch_at(hot_threshold.time) {
+			epoch.register_hot_threshold(&hot_threshold);
+		}
+
+		self.hot_thresholds.push(hot_threshold);
+	}
+
+	/// Returns all accesses, if we're keeping an access log
+	pub fn accesses(&self) -> Option<&[Access]> {
+		self.accesses.as_deref()
 	}
 
 	/// Returns all page migrations
 	pub fn page_migrations(&self) -> &HashMap<PagePtr, Vec<PageMigration>> {
 		&self.page_migration
 	}
+
+	/// Returns all transparent huge page splits and collapses
+	pub fn thp_events(&self) -> &[ThpEvent] {
+		&self.thp_events
+	}
+
+	/// Returns all hot threshold changes
+	pub fn hot_thresholds(&self) -> &[HotThreshold] {
+		&self.hot_thresholds
+	}
+
+	/// Returns all finished epochs
+	pub fn finished_epochs(&self) -> &[Epoch] {
+		&self.epochs
+	}
+
+	/// Returns all epochs, including the current, unfinished, one.
+	///
+	/// The occupancies of the current epoch are only set once it finishes, so
+	/// we fill them in with the current occupancies.
+	pub fn epochs(&self) -> impl Iterator<Item = Epoch> + '_ {
+		let cur_epoch = self.cur_epoch.clone().map(|mut epoch| {
+			self.snapshot_occupancies(&mut epoch);
+			epoch
+		});
+
+		self.epochs.iter().cloned().chain(cur_epoch)
+	}
+
+	/// Returns the totals of the whole run, as a single epoch
+	pub fn total(&self) -> Epoch {
+		let mut total = self.total.clone().unwrap_or_else(|| Epoch::new(0));
+		self.snapshot_occupancies(&mut total);
+		total
+	}
+
+	/// Returns the number of unique pages accessed
+	pub fn unique_pages(&self) -> usize {
+		self.page_migration.len()
+	}
+
+	/// Returns the writes to each page, by memory, including migrations into it.
+	///
+	/// Since the trace only has page granularity, each write is assumed to be to the same cache line.
+	pub fn page_writes(&self) -> &[HashMap<PagePtr, u64>] {
+		&self.page_writes
+	}
+
+	/// Returns the distribution of writes per page of each memory
+	pub fn page_write_distributions(&self) -> Vec<WriteDistribution> {
+		self.page_writes.iter().map(WriteDistribution::new).collect()
+	}
+
+	/// Returns the epoch containing `time`, finishing the current one if `time` is past it.
+	///
+	/// Returns `None` if we aren't keeping epochs.
+	fn epoch_at(&mut self, time: u64) -> Option<&mut Epoch> {
+		let epoch_length = self.epoch_length?;
+
+		// Finish the current epoch, if we're past it
+		if let (Some(epoch), EpochLength::Time(length)) = (&self.cur_epoch, epoch_length) {
+			if time >= epoch.start_time + length {
+				self.finish_epoch();
+			}
+		}
+
+		let epoch = self.cur_epoch.get_or_insert_with(|| {
+			// Note: When using time, we align the epochs with the first, so that
+			//       empty epochs are simply skipped.
+			let start_time = match (self.epochs.last(), epoch_length) {
+				(Some(prev_epoch), EpochLength::Time(length)) =>
+					prev_epoch.start_time + time.saturating_sub(prev_epoch.start_time) / length * length,
+				_ => time,
+			};
+
+			// Note: The hot threshold stays the same until changed, so we carry it over
+			let mut epoch = Epoch::new(start_time);
+			epoch.hot_threshold = self.hot_thresholds.last().map(|hot_threshold| hot_threshold.threshold);
+			epoch
+		});
+
+		Some(epoch)
+	}
+
+	/// Finishes the current epoch, if any
+	fn finish_epoch(&mut self) {
+		if let Some(mut epoch) = self.cur_epoch.take() {
+			self.snapshot_occupancies(&mut epoch);
+			self.epochs.push(epoch);
+		}
+	}
+
+	/// Snapshots the current occupancies and generation sizes into `epoch`
+	fn snapshot_occupancies(&self, epoch: &mut Epoch) {
+		let memories_len = self.occupancies.len().max(self.generation_sizes.len());
+		if epoch.memories.len() < memories_len {
+			epoch.memories.resize(memories_len, EpochMemory::default());
+		}
+
+		for (epoch_mem, &occupancy) in epoch.memories.iter_mut().zip(&self.occupancies) {
+			epoch_mem.occupancy = occupancy;
+		}
+		for (epoch_mem, generation_sizes) in epoch.memories.iter_mut().zip(&self.generation_sizes) {
+			epoch_mem.generation_sizes.clone_from(generation_sizes);
+		}
+	}
+
+	/// Returns the writes to each page of memory `mem_idx`
+	fn page_writes_mut(&mut self, mem_idx: MemIdx) -> &mut HashMap<PagePtr, u64> {
+		let mem_idx = mem_idx.to_usize();
+		if mem_idx >= self.page_writes.len() {
+			self.page_writes.resize_with(mem_idx + 1, HashMap::new);
+		}
+
+		&mut self.page_writes[mem_idx]
+	}
+
+	/// Returns the occupancy of memory `mem_idx`
+	fn occupancy_mut(&mut self, mem_idx: MemIdx) -> &mut usize {
+		let mem_idx = mem_idx.to_usize();
+		if mem_idx >= self.occupancies.len() {
+			self.occupancies.resize(mem_idx + 1, 0);
+		}
+
+		&mut self.occupancies[mem_idx]
+	}
 }
 
 impl Default for Statistics {
 	fn default() -> Self {
-		Self::new()
+		Self::new(None, true)
 	}
 }
 
@@ -76,6 +354,15 @@ pub struct Access {
 
 	/// Caused a global cooling?
 	pub caused_cooling: bool,
+
+	/// Simulated latency, including any queueing delay
+	pub latency: FemtoDuration,
+
+	/// Queueing delay due to the memory's bandwidth
+	pub queueing_delay: FemtoDuration,
+
+	/// Whether the access hit in the TLB, if any
+	pub tlb_hit: Option<bool>,
 }
 
 /// Access kind for [`Access`]
@@ -96,6 +383,9 @@ pub enum AccessMem {
 
 	/// Page resided in memory
 	Resided(MemIdx),
+
+	/// Page was swapped in from swap to memory
+	SwappedIn(MemIdx),
 }
 
 /// Page migration
@@ -109,4 +399,319 @@ pub struct PageMigration {
 
 	/// Memory
 	pub cur_mem_idx: MemIdx,
+
+	/// Kind, if migrated between memories.
+	///
+	/// See [`Memories::migration_kind`](super::Memories::migration_kind).
+	pub kind: Option<MigrationKind>,
+}
+
+/// Transparent huge page split or collapse
+#[derive(Clone, Copy, Debug)]
+pub struct ThpEvent {
+	/// Timestamp
+	pub time: u64,
+
+	/// Region pointer
+	pub region_ptr: u64,
+
+	/// Kind
+	pub kind: ThpEventKind,
+
+	/// Latency
+	pub cost: FemtoDuration,
+}
+
+/// Kind of [`ThpEvent`]
+#[derive(Clone, Copy, Debug)]
+pub enum ThpEventKind {
+	/// Huge page was split into pages
+	Split,
+
+	/// Region was collapsed into a huge page
+	Collapse,
+}
+
+/// Hot threshold change
+#[derive(Clone, Copy, Debug)]
+pub struct HotThreshold {
+	/// Timestamp
+	pub time: u64,
+
+	/// Accesses for a page to be hot
+	pub threshold: usize,
+}
+
+/// Epoch length
+#[derive(Clone, Copy, Debug)]
+pub enum EpochLength {
+	/// Trace time
+	Time(u64),
+
+	/// Number of trace records
+	Records(usize),
+}
+
+/// Epoch summary
+#[derive(Clone, Debug)]
+pub struct Epoch {
+	/// Start time
+	pub start_time: u64,
+
+	/// End time (exclusive)
+	pub end_time: u64,
+
+	/// Records
+	pub records: usize,
+
+	/// Trace records, including those that didn't reach the classifier as an access
+	pub trace_records: usize,
+
+	/// Memories
+	pub memories: Vec<EpochMemory>,
+
+	/// Promotions (migrations to a faster memory)
+	pub promotions: usize,
+
+	/// Demotions (migrations to a slower memory)
+	pub demotions: usize,
+
+	/// Global coolings
+	pub coolings: usize,
+
+	/// Accesses dropped due to all memories being full
+	pub dropped_accesses: usize,
+
+	/// Major faults (accesses that swapped in a page)
+	pub major_faults: usize,
+
+	/// TLB hits
+	pub tlb_hits: usize,
+
+	/// TLB misses
+	pub tlb_misses: usize,
+
+	/// Transparent huge page splits
+	pub thp_splits: usize,
+
+	/// Transparent huge page collapses
+	pub thp_collapses: usize,
+
+	/// Latency of all transparent huge page splits and collapses
+	pub thp_latency: FemtoDuration,
+
+	/// Latest hot threshold, for classifiers that adapt it
+	pub hot_threshold: Option<usize>,
+
+	/// Simulated latency
+	pub latency: FemtoDuration,
+}
+
+impl Epoch {
+	/// Creates a new, empty, epoch
+	fn new(start_time: u64) -> Self {
+		Self {
+			start_time,
+			end_time: start_time,
+			records: 0,
+			trace_records: 0,
+			memories: vec![],
+			promotions: 0,
+			demotions: 0,
+			coolings: 0,
+			dropped_accesses: 0,
+			major_faults: 0,
+			tlb_hits: 0,
+			tlb_misses: 0,
+			thp_splits: 0,
+			thp_collapses: 0,
+			thp_latency: FemtoDuration::ZERO,
+			hot_threshold: None,
+			latency: FemtoDuration::ZERO,
+		}
+	}
+
+	/// Registers an access on this epoch
+	fn register_access(&mut self, access: &Access) {
+		self.end_time = self.end_time.max(access.time + 1);
+		self.records += 1;
+		self.latency += access.latency;
+		self.coolings += usize::from(access.caused_cooling);
+		match access.tlb_hit {
+			Some(true) => self.tlb_hits += 1,
+			Some(false) => self.tlb_misses += 1,
+			None => (),
+		}
+
+		let (mem_idx, faulted) = match access.mem {
+			AccessMem::Mapped(mem_idx) => (mem_idx, true),
+			AccessMem::Resided(mem_idx) => (mem_idx, false),
+			AccessMem::SwappedIn(mem_idx) => {
+				self.major_faults += 1;
+				(mem_idx, true)
+			},
+		};
+		let epoch_mem = self.memory_mut(mem_idx);
+		match access.kind {
+			AccessKind::Read => {
+				epoch_mem.reads += 1;
+				epoch_mem.read_bytes += memories::ACCESS_BYTES;
+			},
+			AccessKind::Write => {
+				epoch_mem.writes += 1;
+				epoch_mem.write_bytes += memories::ACCESS_BYTES;
+			},
+		}
+		epoch_mem.faults += usize::from(faulted);
+		epoch_mem.queueing_delay += access.queueing_delay;
+	}
+
+	/// Registers a page migration on this epoch
+	fn register_page_migration(&mut self, page_migration: &PageMigration) {
+		self.end_time = self.end_time.max(page_migration.time + 1);
+		match page_migration.kind {
+			Some(MigrationKind::Promotion) => self.promotions += 1,
+			Some(MigrationKind::Demotion) => self.demotions += 1,
+			None => (),
+		}
+
+		if let Some(prev_mem_idx) = page_migration.prev_mem_idx {
+			self.memory_mut(prev_mem_idx).read_bytes += memories::PAGE_BYTES;
+			self.memory_mut(page_migration.cur_mem_idx).write_bytes += memories::PAGE_BYTES;
+		}
+	}
+
+	/// Registers a transparent huge page split or collapse on this epoch
+	fn register_thp_event(&mut self, thp_event: &ThpEvent) {
+		self.end_time = self.end_time.max(thp_event.time + 1);
+		match thp_event.kind {
+			ThpEventKind::Split => self.thp_splits += 1,
+			ThpEventKind::Collapse => self.thp_collapses += 1,
+		}
+		self.thp_latency += thp_event.cost;
+	}
+
+	/// Registers a change of the hot threshold on this epoch
+	fn register_hot_threshold(&mut self, hot_threshold: &HotThreshold) {
+		self.end_time = self.end_time.max(hot_threshold.time + 1);
+		self.hot_threshold = Some(hot_threshold.threshold);
+	}
+
+	/// Registers a trace record on this epoch
+	fn register_trace_record(&mut self, time: u64) {
+		self.end_time = self.end_time.max(time + 1);
+		self.trace_records += 1;
+	}
+
+	/// Registers a dropped access on this epoch
+	fn register_dropped_access(&mut self, time: u64) {
+		self.end_time = self.end_time.max(time + 1);
+		self.dropped_accesses += 1;
+	}
+
+	/// Returns the hit rate of the fastest memory.
+	///
+	/// Accesses that faulted the page in are considered misses.
+	pub fn fast_hit_rate(&self) -> f64 {
+		let fast_hits = self
+			.memories
+			.first()
+			.map_or(0, |mem| mem.reads + mem.writes - mem.faults);
+		match self.records {
+			0 => 0.0,
+			records => fast_hits as f64 / records as f64,
+		}
+	}
+
+	/// Returns the hit rate of the TLB
+	pub fn tlb_hit_rate(&self) -> f64 {
+		match self.tlb_hits + self.tlb_misses {
+			0 => 0.0,
+			accesses => self.tlb_hits as f64 / accesses as f64,
+		}
+	}
+
+	/// Returns the summary of memory `mem_idx`
+	fn memory_mut(&mut self, mem_idx: MemIdx) -> &mut EpochMemory {
+		let mem_idx = mem_idx.to_usize();
+		if mem_idx >= self.memories.len() {
+			self.memories.resize(mem_idx + 1, EpochMemory::default());
+		}
+
+		&mut self.memories[mem_idx]
+	}
+}
+
+/// Epoch memory summary
+#[derive(Clone, Default, Debug)]
+pub struct EpochMemory {
+	/// Reads
+	pub reads: usize,
+
+	/// Writes
+	pub writes: usize,
+
+	/// Faults
+	pub faults: usize,
+
+	/// Bytes read, including migrations
+	pub read_bytes: u64,
+
+	/// Bytes written, including migrations
+	pub write_bytes: u64,
+
+	/// Queueing delay due to the memory's bandwidth
+	pub queueing_delay: FemtoDuration,
+
+	/// Occupancy at the end of the epoch
+	pub occupancy: usize,
+
+	/// Pages of each generation at the end of the epoch, from the oldest to the youngest.
+	///
+	/// Empty for classifiers without generations.
+	pub generation_sizes: Vec<usize>,
+}
+
+/// Distribution of writes per page
+#[derive(Clone, Copy, Default, Debug)]
+pub struct WriteDistribution {
+	/// Pages written
+	pub pages: usize,
+
+	/// Total writes
+	pub total: u64,
+
+	/// Max writes to a single page
+	pub max: u64,
+
+	/// Median writes per page
+	pub p50: u64,
+
+	/// 90th percentile of writes per page
+	pub p90: u64,
+
+	/// 99th percentile of writes per page
+	pub p99: u64,
+}
+
+impl WriteDistribution {
+	/// Calculates the distribution of `page_writes`
+	fn new(page_writes: &HashMap<PagePtr, u64>) -> Self {
+		let mut writes = page_writes.values().copied().collect::<Vec<_>>();
+		writes.sort_unstable();
+
+		// Note: We use the nearest-rank method for the percentiles
+		let percentile = |percentile: f64| match writes.len() {
+			0 => 0,
+			len => writes[((percentile * len as f64).ceil() as usize).clamp(1, len) - 1],
+		};
+		Self {
+			pages: writes.len(),
+			total: writes.iter().sum(),
+			max:   writes.last().copied().unwrap_or(0),
+			p50:   percentile(0.50),
+			p90:   percentile(0.90),
+			p99:   percentile(0.99),
+		}
+	}
 }
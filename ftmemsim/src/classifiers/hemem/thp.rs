@@ -0,0 +1,137 @@
+//! Transparent huge pages
+//!
+//! Tracks which `2MiB` regions are currently mapped as a huge page, splitting
+//! them when the hotness of their sub-pages differs a lot, and collapsing them
+//! back once it evens out.
+//!
+//! Note: Pages are still placed in the memories with `4KiB` granularity, so
+//!       the huge pages only affect the translation and the cost of the
+//!       splits and collapses themselves.
+
+// Imports
+use {
+	super::statistics::{ThpEvent, ThpEventKind},
+	ftmemsim_util::FemtoDuration,
+	std::collections::HashMap,
+};
+
+/// Transparent huge page tracker
+#[derive(Debug)]
+pub struct Thp {
+	/// Config
+	config: ThpConfig,
+
+	/// All regions accessed
+	regions: HashMap<u64, Region>,
+}
+
+impl Thp {
+	/// Huge page size
+	pub const HUGE_PAGE_SIZE: u64 = 0x20_0000;
+	/// Page size
+	pub const PAGE_SIZE: u64 = 0x1000;
+	/// Number of sub-pages in each huge page
+	const SUB_PAGES_LEN: usize = (Self::HUGE_PAGE_SIZE / Self::PAGE_SIZE) as usize;
+
+	/// Creates a new tracker
+	///
+	/// # Errors
+	/// Returns an error if `check_accesses` is 0, or if the collapse
+	/// skew isn't below the split skew.
+	pub fn new(config: ThpConfig) -> Result<Self, anyhow::Error> {
+		anyhow::ensure!(config.check_accesses != 0, "THP check accesses must not be 0");
+		anyhow::ensure!(
+			config.collapse_skew < config.split_skew,
+			"THP collapse skew ({}) must be below the split skew ({})",
+			config.collapse_skew,
+			config.split_skew
+		);
+
+		Ok(Self {
+			config,
+			regions: HashMap::new(),
+		})
+	}
+
+	/// Returns the size of the page `addr` is currently mapped with
+	pub fn page_size(&self, addr: u64) -> u64 {
+		match self.regions.get(&(addr & !(Self::HUGE_PAGE_SIZE - 1))) {
+			Some(region) if !region.huge => Self::PAGE_SIZE,
+			_ => Self::HUGE_PAGE_SIZE,
+		}
+	}
+
+	/// Registers an access to `addr`, returning any split or collapse it caused.
+	///
+	/// Regions start as a huge page and, every `check_accesses` accesses, have the skew of their
+	/// sub-pages' accesses checked, that is, the ratio between the accesses to the hottest sub-page
+	/// and the mean accesses per sub-page.
+	pub fn access(&mut self, time: u64, addr: u64) -> Option<ThpEvent> {
+		let region_ptr = addr & !(Self::HUGE_PAGE_SIZE - 1);
+		let region = self.regions.entry(region_ptr).or_insert_with(|| Region {
+			huge:              true,
+			accesses:          0,
+			sub_page_accesses: vec![0; Self::SUB_PAGES_LEN].into_boxed_slice(),
+		});
+
+		let sub_page_idx = ((addr - region_ptr) / Self::PAGE_SIZE) as usize;
+		region.sub_page_accesses[sub_page_idx] += 1;
+		region.accesses += 1;
+		if region.accesses < self.config.check_accesses {
+			return None;
+		}
+
+		// Note: Accesses are counted anew after each check, so the skew reflects recent behavior
+		let max_sub_page_accesses = region.sub_page_accesses.iter().copied().max().unwrap_or(0);
+		let mean_sub_page_accesses = region.accesses as f64 / Self::SUB_PAGES_LEN as f64;
+		let skew = max_sub_page_accesses as f64 / mean_sub_page_accesses;
+		region.accesses = 0;
+		region.sub_page_accesses.fill(0);
+
+		let (kind, cost) = match region.huge {
+			true if skew >= self.config.split_skew => (ThpEventKind::Split, self.config.split_latency),
+			false if skew <= self.config.collapse_skew => (ThpEventKind::Collapse, self.config.collapse_latency),
+			_ => return None,
+		};
+		region.huge = matches!(kind, ThpEventKind::Collapse);
+
+		Some(ThpEvent {
+			time,
+			region_ptr,
+			kind,
+			cost,
+		})
+	}
+}
+
+/// Transparent huge page config
+#[derive(Clone, Copy, Debug)]
+pub struct ThpConfig {
+	/// Accesses to a region between each check
+	pub check_accesses: u64,
+
+	/// Minimum skew to split a huge page
+	pub split_skew: f64,
+
+	/// Maximum skew to collapse a region back into a huge page
+	pub collapse_skew: f64,
+
+	/// Latency of splitting a huge page
+	pub split_latency: FemtoDuration,
+
+	/// Latency of collapsing a region into a huge page
+	pub collapse_latency: FemtoDuration,
+}
+
+/// Region
+#[derive(Clone, Debug)]
+struct Region {
+	/// Whether the region is mapped as a huge page
+	huge: bool,
+
+	/// Accesses since the last check
+	accesses: u64,
+
+	/// Accesses to each sub-page since the last check
+	sub_page_accesses: Box<[u64]>,
+}
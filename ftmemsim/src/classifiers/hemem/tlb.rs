@@ -0,0 +1,172 @@
+//! TLB
+
+// Imports
+use {ftmemsim_util::FemtoDuration, std::fmt};
+
+/// TLB.
+///
+/// Holds a set-associative array of entries for each page size, and charges
+/// a page walk on every miss.
+#[derive(Debug)]
+pub struct Tlb {
+	/// Entries of each page size
+	page_sizes: Vec<TlbEntries>,
+
+	/// Page size the memory is mapped with, when not using transparent huge pages
+	page_size: u64,
+
+	/// Page walk latency
+	page_walk_latency: FemtoDuration,
+}
+
+impl Tlb {
+	/// Creates a new TLB, for memory mapped with pages of `page_size`.
+	///
+	/// # Errors
+	/// Returns an error if there are no entries for `page_size`.
+	pub fn new(
+		page_sizes: Vec<TlbEntries>,
+		page_size: u64,
+		page_walk_latency: FemtoDuration,
+	) -> Result<Self, anyhow::Error> {
+		anyhow::ensure!(
+			page_sizes.iter().any(|entries| entries.page_size == page_size),
+			"TLB has no entries for page size {page_size}"
+		);
+
+		Ok(Self {
+			page_sizes,
+			page_size,
+			page_walk_latency,
+		})
+	}
+
+	/// Returns the entries of each page size
+	pub fn page_sizes(&self) -> &[TlbEntries] {
+		&self.page_sizes
+	}
+
+	/// Returns the page size the memory is mapped with, by default
+	pub fn page_size(&self) -> u64 {
+		self.page_size
+	}
+
+	/// Returns whether there are entries for `page_size`
+	pub fn has_page_size(&self, page_size: u64) -> bool {
+		self.page_sizes.iter().any(|entries| entries.page_size == page_size)
+	}
+
+	/// Returns the page walk latency
+	pub fn page_walk_latency(&self) -> FemtoDuration {
+		self.page_walk_latency
+	}
+
+	/// Translates `addr`, mapped with a page of `page_size`, returning whether it hit.
+	///
+	/// # Panics
+	/// Panics if there are no entries for `page_size`.
+	pub fn access(&mut self, addr: u64, page_size: u64) -> bool {
+		self.page_sizes
+			.iter_mut()
+			.find(|entries| entries.page_size == page_size)
+			.unwrap_or_else(|| panic!("TLB has no entries for page size {page_size}"))
+			.access(addr)
+	}
+}
+
+/// TLB entries for a page size
+pub struct TlbEntries {
+	/// Page size
+	page_size: u64,
+
+	/// Associativity
+	associativity: usize,
+
+	/// Sets.
+	///
+	/// Each set holds the page numbers, ordered from the most to the least recently used.
+	sets: Vec<Vec<u64>>,
+
+	/// Hits
+	hits: usize,
+
+	/// Misses
+	misses: usize,
+}
+
+impl TlbEntries {
+	/// Creates new TLB entries
+	///
+	/// # Errors
+	/// Returns an error if the page size isn't a power of two of at least 4KiB,
+	/// or if `entries` isn't a non-zero multiple of `associativity`.
+	pub fn new(page_size: u64, entries: usize, associativity: usize) -> Result<Self, anyhow::Error> {
+		anyhow::ensure!(
+			page_size.is_power_of_two() && page_size >= 0x1000,
+			"TLB page size must be a power of two of at least 4KiB"
+		);
+		anyhow::ensure!(associativity != 0, "TLB associativity must not be 0");
+		anyhow::ensure!(
+			entries != 0 && entries.is_multiple_of(associativity),
+			"TLB entries must be a non-zero multiple of the associativity ({associativity})"
+		);
+
+		Ok(Self {
+			page_size,
+			associativity,
+			sets: vec![Vec::with_capacity(associativity); entries / associativity],
+			hits: 0,
+			misses: 0,
+		})
+	}
+
+	/// Returns the page size
+	pub fn page_size(&self) -> u64 {
+		self.page_size
+	}
+
+	/// Returns the hits
+	pub fn hits(&self) -> usize {
+		self.hits
+	}
+
+	/// Returns the misses
+	pub fn misses(&self) -> usize {
+		self.misses
+	}
+
+	/// Translates `addr`, returning whether it hit
+	fn access(&mut self, addr: u64) -> bool {
+		let page_num = addr / self.page_size;
+		let set_idx = (page_num % self.sets.len() as u64) as usize;
+		let set = &mut self.sets[set_idx];
+
+		// If we hit, move the entry to the front
+		if let Some(entry_idx) = set.iter().position(|&entry| entry == page_num) {
+			set[..=entry_idx].rotate_right(1);
+			self.hits += 1;
+			return true;
+		}
+
+		// Else evict the least recently used entry, if the set is full, and insert it
+		self.misses += 1;
+		if set.len() >= self.associativity {
+			set.pop();
+		}
+		set.insert(0, page_num);
+
+		false
+	}
+}
+
+impl fmt::Debug for TlbEntries {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("TlbEntries")
+			.field("page_size", &self.page_size)
+			.field("associativity", &self.associativity)
+			.field("sets", &self.sets.len())
+			.field("hits", &self.hits)
+			.field("misses", &self.misses)
+			.finish()
+	}
+}
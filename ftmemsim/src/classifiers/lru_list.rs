@@ -0,0 +1,100 @@
+//! LRU list
+
+// Imports
+use std::collections::{BTreeMap, HashMap};
+
+/// LRU list.
+///
+/// Keeps items ordered from the most recently used (the front) to the least
+/// recently used (the back), with logarithmic insertion and removal of any item.
+#[derive(Clone, Debug)]
+pub struct LruList<T> {
+	/// Items, by their position
+	items: BTreeMap<i64, T>,
+
+	/// Position of each item
+	positions: HashMap<T, i64>,
+
+	/// Next position at the front
+	next_front: i64,
+
+	/// Next position at the back
+	next_back: i64,
+}
+
+impl<T: Copy + Eq + std::hash::Hash> LruList<T> {
+	/// Creates an empty list
+	pub fn new() -> Self {
+		Self {
+			items:      BTreeMap::new(),
+			positions:  HashMap::new(),
+			next_front: 0,
+			next_back:  -1,
+		}
+	}
+
+	/// Returns the number of items
+	pub fn len(&self) -> usize {
+		self.items.len()
+	}
+
+	/// Returns if there are no items
+	pub fn is_empty(&self) -> bool {
+		self.items.is_empty()
+	}
+
+	/// Returns if `item` is in the list
+	pub fn contains(&self, item: T) -> bool {
+		self.positions.contains_key(&item)
+	}
+
+	/// Inserts `item` at the front, moving it if it was already in the list
+	pub fn push_front(&mut self, item: T) {
+		self.remove(item);
+		self.items.insert(self.next_front, item);
+		self.positions.insert(item, self.next_front);
+		self.next_front += 1;
+	}
+
+	/// Inserts `item` at the back, moving it if it was already in the list
+	pub fn push_back(&mut self, item: T) {
+		self.remove(item);
+		self.items.insert(self.next_back, item);
+		self.positions.insert(item, self.next_back);
+		self.next_back -= 1;
+	}
+
+	/// Removes the item at the back, the least recently used
+	pub fn pop_back(&mut self) -> Option<T> {
+		let (_, item) = self.items.pop_first()?;
+		self.positions.remove(&item);
+		Some(item)
+	}
+
+	/// Returns the item at the back, the least recently used
+	pub fn back(&self) -> Option<T> {
+		self.items.first_key_value().map(|(_, &item)| item)
+	}
+
+	/// Removes `item`, returning if it was in the list
+	pub fn remove(&mut self, item: T) -> bool {
+		match self.positions.remove(&item) {
+			Some(position) => {
+				self.items.remove(&position);
+				true
+			},
+			None => false,
+		}
+	}
+
+	/// Returns an iterator over all items, from the back to the front
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item = T> + '_ {
+		self.items.values().copied()
+	}
+}
+
+impl<T: Copy + Eq + std::hash::Hash> Default for LruList<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
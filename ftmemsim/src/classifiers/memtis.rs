@@ -0,0 +1,328 @@
+//! Memtis classifier
+//!
+//! Models Memtis' dynamic hot threshold: A histogram of the access counts of all
+//! pages is kept, with power of 2 bins, and every adaptation period the hot threshold
+//! is set to the lowest bin whose pages, along with those of all hotter bins, still
+//! fit in the fastest memory.
+//!
+//! Hot pages are promoted when accessed, demoting a cold page of the promotion target,
+//! by least recent use, if it's full. Once any page reaches the cooling threshold, all
+//! access counts are halved, which simply shifts the histogram down a bin.
+
+// Imports
+use {
+	super::{
+		hemem::{memories::MemIdx, statistics, PagePtr},
+		lru_list::LruList,
+		observer::Observer,
+		tiered::TieredMemory,
+	},
+	crate::sim,
+	anyhow::Context,
+	ftmemsim_util::FemtoDuration,
+	std::{collections::BTreeMap, fmt},
+};
+
+/// Memtis classifier
+#[derive(Debug)]
+pub struct Memtis {
+	/// Config
+	config: Config,
+
+	/// Tiered memory
+	tiered: TieredMemory,
+
+	/// All pages
+	pages: BTreeMap<PagePtr, Page>,
+
+	/// Pages per bin.
+	///
+	/// Bin `n` contains the pages with `[2^(n-1), 2^n)` accesses, with bin 0 containing
+	/// the pages with no accesses.
+	histogram: Vec<usize>,
+
+	/// LRU list of each memory
+	lru_lists: BTreeMap<MemIdx, LruList<PagePtr>>,
+
+	/// Current cooling clock tick
+	cooling_clock_tick: usize,
+
+	/// Current hot threshold.
+	///
+	/// Pages with at least this many accesses are hot.
+	hot_threshold: usize,
+
+	/// Next time to adapt the hot threshold
+	next_adaptation_time: Option<u64>,
+}
+
+impl Memtis {
+	/// Number of histogram bins
+	pub const HISTOGRAM_BINS: usize = usize::BITS as usize + 1;
+
+	/// Creates a Memtis classifier
+	///
+	/// # Errors
+	/// Returns an error if the adaptation period or cooling threshold are 0.
+	pub fn new(config: Config, tiered: TieredMemory) -> Result<Self, anyhow::Error> {
+		anyhow::ensure!(config.adaptation_period != 0, "Adaptation period must not be 0");
+		anyhow::ensure!(config.cooling_threshold != 0, "Cooling threshold must not be 0");
+
+		Ok(Self {
+			config,
+			tiered,
+			pages: BTreeMap::new(),
+			histogram: vec![0; Self::HISTOGRAM_BINS],
+			lru_lists: BTreeMap::new(),
+			cooling_clock_tick: 0,
+			hot_threshold: usize::MAX,
+			next_adaptation_time: None,
+		})
+	}
+
+	/// Returns the statistics
+	pub fn statistics(&self) -> &statistics::Statistics {
+		self.tiered.statistics()
+	}
+
+	/// Returns the histogram bin of a page with `accesses` accesses
+	fn bin(accesses: usize) -> usize {
+		(usize::BITS - accesses.leading_zeros()) as usize
+	}
+
+	/// Returns the accesses of a page, cooled to the current cooling clock tick
+	fn page_accesses(&self, page_ptr: PagePtr) -> usize {
+		let page = self.pages.get(&page_ptr).expect("Page wasn't mapped");
+		let offset = (self.cooling_clock_tick - page.cooling_clock_tick).min(usize::BITS as usize - 1);
+		page.accesses >> offset
+	}
+
+	/// Registers an access to a page, returning it's accesses before and after it.
+	fn register_page_access(&mut self, page_ptr: PagePtr) -> (usize, usize) {
+		let prev_accesses = self.page_accesses(page_ptr);
+		let cur_accesses = prev_accesses + 1;
+
+		let page = self.pages.get_mut(&page_ptr).expect("Page wasn't mapped");
+		page.accesses = cur_accesses;
+		page.cooling_clock_tick = self.cooling_clock_tick;
+
+		self.histogram[Self::bin(prev_accesses)] -= 1;
+		self.histogram[Self::bin(cur_accesses)] += 1;
+
+		(prev_accesses, cur_accesses)
+	}
+
+	/// Halves the accesses of all pages
+	fn cool_all_pages(&mut self) {
+		// Note: Halving the accesses moves every page exactly one bin down, so
+		//       we may shift the histogram instead of visiting every page, which
+		//       are only cooled when next accessed.
+		self.cooling_clock_tick += 1;
+		self.histogram[0] += self.histogram[1];
+		self.histogram.copy_within(2.., 1);
+		self.histogram[Self::HISTOGRAM_BINS - 1] = 0;
+	}
+
+	/// Adapts the hot threshold, if it's been long enough since the last adaptation.
+	///
+	/// Sets the threshold to the lowest bin such that all pages in it and the bins above
+	/// fit in the fastest memory.
+	fn adapt_hot_threshold(&mut self, cur_time: u64) {
+		if self.next_adaptation_time.is_some_and(|next_time| cur_time < next_time) {
+			return;
+		}
+		self.next_adaptation_time = Some(cur_time + self.config.adaptation_period);
+
+		let fast_capacity = self
+			.tiered
+			.memories()
+			.iter()
+			.next()
+			.map_or(0, |(_, mem)| mem.page_capacity());
+		let mut hot_pages = 0;
+		let mut hot_bin = Self::HISTOGRAM_BINS;
+		for bin in (1..Self::HISTOGRAM_BINS).rev() {
+			hot_pages += self.histogram[bin];
+			if hot_pages > fast_capacity {
+				break;
+			}
+			hot_bin = bin;
+		}
+
+		let hot_threshold = 1_usize.checked_shl(hot_bin as u32 - 1).unwrap_or(usize::MAX);
+		if hot_threshold != self.hot_threshold {
+			tracing::trace!(hot_threshold, "Adapted hot threshold");
+			self.hot_threshold = hot_threshold;
+			self.tiered.register_hot_threshold(statistics::HotThreshold {
+				time:      cur_time,
+				threshold: hot_threshold,
+			});
+		}
+	}
+
+	/// Promotes a page from memory `mem_idx` to the nearest promotion target,
+	/// demoting a cold page first if it's full.
+	fn promote_page(&mut self, cur_time: u64, page_ptr: PagePtr, mem_idx: MemIdx) -> Result<(), anyhow::Error> {
+		let dst_mem_idx = *self
+			.tiered
+			.memories()
+			.promotion_targets(mem_idx)
+			.first()
+			.context("Memory has no promotion targets")?;
+		if self.tiered.memories().get(dst_mem_idx).is_full() {
+			self.demote_cold_page(cur_time, dst_mem_idx)
+				.context("Unable to make room in promotion target")?;
+		}
+
+		self.migrate_page(cur_time, page_ptr, mem_idx, dst_mem_idx)
+	}
+
+	/// Demotes the least recently used cold page of memory `mem_idx` to the nearest demotion target with space
+	fn demote_cold_page(&mut self, cur_time: u64, mem_idx: MemIdx) -> Result<(), anyhow::Error> {
+		let dst_mem_idx = self
+			.tiered
+			.memories()
+			.demotion_targets(mem_idx)
+			.iter()
+			.copied()
+			.find(|&dst_mem_idx| !self.tiered.memories().get(dst_mem_idx).is_full())
+			.context("All demotion targets were full")?;
+		let page_ptr = self
+			.lru_lists
+			.get(&mem_idx)
+			.and_then(|lru_list| {
+				lru_list
+					.iter()
+					.find(|&page_ptr| self.page_accesses(page_ptr) < self.hot_threshold)
+			})
+			.context("Memory had no cold pages")?;
+
+		self.migrate_page(cur_time, page_ptr, mem_idx, dst_mem_idx)
+	}
+
+	/// Migrates a page from memory `src_mem_idx` to `dst_mem_idx`, as it's most recently used page
+	fn migrate_page(
+		&mut self,
+		cur_time: u64,
+		page_ptr: PagePtr,
+		src_mem_idx: MemIdx,
+		dst_mem_idx: MemIdx,
+	) -> Result<(), anyhow::Error> {
+		self.tiered.migrate_page(cur_time, page_ptr, dst_mem_idx)?;
+		self.lru_lists.entry(src_mem_idx).or_default().remove(page_ptr);
+		self.lru_lists.entry(dst_mem_idx).or_default().push_front(page_ptr);
+
+		Ok(())
+	}
+}
+
+impl sim::Classifier for Memtis {
+	fn register_trace(&mut self, trace: sim::Trace) {
+		self.tiered.register_trace_record(trace.record.time);
+	}
+
+	fn handle_trace(&mut self, trace: sim::Trace) -> Result<(), anyhow::Error> {
+		tracing::trace!(?trace, "Received trace");
+		let page_ptr = PagePtr::new(trace.record.addr);
+		let cur_time = trace.record.time;
+
+		self.adapt_hot_threshold(cur_time);
+
+		// Map the page if it doesn't exist
+		let (access_mem, mem_idx) = match self.tiered.page_mem(page_ptr) {
+			Some(mem_idx) => (statistics::AccessMem::Resided(mem_idx), mem_idx),
+			None => {
+				let mem_idx = match self.tiered.map_page_first_fit(cur_time, page_ptr) {
+					Ok(mem_idx) => mem_idx,
+					Err(err) =>
+						return self
+							.tiered
+							.handle_oom(cur_time, page_ptr, err)
+							.context("Unable to map page"),
+				};
+				self.pages.insert(page_ptr, Page {
+					accesses:           0,
+					cooling_clock_tick: self.cooling_clock_tick,
+				});
+				self.histogram[0] += 1;
+				(statistics::AccessMem::Mapped(mem_idx), mem_idx)
+			},
+		};
+		self.lru_lists.entry(mem_idx).or_default().push_front(page_ptr);
+
+		// Register the access, cooling all pages if it's over the threshold
+		let (prev_accesses, cur_accesses) = self.register_page_access(page_ptr);
+		let caused_cooling = cur_accesses >= self.config.cooling_threshold;
+		if caused_cooling {
+			self.cool_all_pages();
+			self.tiered.register_cool_all(cur_time);
+		}
+
+		// Note: The access happens before the promotion, so it's served by the previous memory.
+		self.tiered.access(
+			trace.record,
+			access_mem,
+			FemtoDuration::ZERO,
+			prev_accesses,
+			cur_accesses,
+			caused_cooling,
+		);
+
+		// Then promote the page if it's hot
+		let is_slow = !self.tiered.memories().promotion_targets(mem_idx).is_empty();
+		if is_slow && self.page_accesses(page_ptr) >= self.hot_threshold {
+			tracing::trace!(?page_ptr, "Page is hot, promoting it");
+			if let Err(err) = self.promote_page(cur_time, page_ptr, mem_idx) {
+				tracing::trace!(?page_ptr, ?err, "Unable to promote page");
+			}
+		}
+
+		Ok(())
+	}
+
+	fn finish(&mut self) {
+		self.tiered.finish();
+	}
+
+	fn add_observer(&mut self, observer: impl Observer + 'static) {
+		self.tiered.add_observer(observer);
+	}
+
+	fn fmt_debug(&mut self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+		// Note: Start with a newline, since we're a multi-line output
+		f.pad("\n")?;
+
+		self.tiered.fmt_debug(f)?;
+		writeln!(f, "Hot threshold: {}", self.hot_threshold)?;
+
+		let last_bin = self.histogram.iter().rposition(|&pages| pages != 0).unwrap_or(0);
+		writeln!(f, "Histogram: {:?}", &self.histogram[..=last_bin])
+	}
+
+	fn debug_stats(&mut self) -> serde_json::Value {
+		let mut stats = self.tiered.debug_stats();
+		stats["hot_threshold"] = serde_json::Value::from(self.hot_threshold);
+		stats["histogram"] = serde_json::Value::from(self.histogram.clone());
+		stats
+	}
+}
+
+/// Configuration
+#[derive(Clone, Debug)]
+pub struct Config {
+	/// Period between each adaptation of the hot threshold (in trace time)
+	pub adaptation_period: u64,
+
+	/// Accesses of a page at which all pages are cooled
+	pub cooling_threshold: usize,
+}
+
+/// Page
+#[derive(Clone, Copy, Debug)]
+struct Page {
+	/// Accesses, as of `cooling_clock_tick`
+	accesses: usize,
+
+	/// Cooling clock tick the accesses were last cooled at
+	cooling_clock_tick: usize,
+}
@@ -0,0 +1,397 @@
+//! MGLRU classifier
+//!
+//! Models Linux's multi-generational LRU: The pages of each memory are kept in
+//! up to `generations` generations, from the oldest to the youngest. Every aging
+//! interval, a new youngest generation is created in each memory, and the accessed
+//! bits of all it's pages are scanned, moving the accessed pages to it. If that
+//! leaves too many generations, the two oldest are merged.
+//!
+//! Pages in slower memories found accessed while already in the youngest generation
+//! are promoted, and full memories demote from their oldest generation to make room,
+//! giving a second chance to pages accessed since the last scan.
+
+// Imports
+use {
+	super::{
+		hemem::{memories::MemIdx, statistics, PagePtr},
+		observer::Observer,
+		tiered::TieredMemory,
+	},
+	crate::sim,
+	anyhow::Context,
+	ftmemsim_util::FemtoDuration,
+	std::{
+		collections::{BTreeMap, BTreeSet, VecDeque},
+		fmt,
+	},
+};
+
+/// MGLRU classifier
+#[derive(Debug)]
+pub struct Mglru {
+	/// Config
+	config: Config,
+
+	/// Tiered memory
+	tiered: TieredMemory,
+
+	/// All pages
+	pages: BTreeMap<PagePtr, Page>,
+
+	/// Generations of each memory
+	generations: BTreeMap<MemIdx, Generations>,
+
+	/// Next time to age
+	next_aging_time: Option<u64>,
+
+	/// Agings
+	agings: usize,
+}
+
+impl Mglru {
+	/// Creates an MGLRU classifier
+	///
+	/// # Errors
+	/// Returns an error if there are less than 2 generations, or if the aging interval is 0.
+	pub fn new(config: Config, tiered: TieredMemory) -> Result<Self, anyhow::Error> {
+		anyhow::ensure!(config.generations >= 2, "Must have at least 2 generations");
+		anyhow::ensure!(config.aging_interval != 0, "Aging interval must not be 0");
+
+		Ok(Self {
+			config,
+			tiered,
+			pages: BTreeMap::new(),
+			generations: BTreeMap::new(),
+			next_aging_time: None,
+			agings: 0,
+		})
+	}
+
+	/// Returns the statistics
+	pub fn statistics(&self) -> &statistics::Statistics {
+		self.tiered.statistics()
+	}
+
+	/// Ages all memories, if it's been long enough since the last aging.
+	///
+	/// Promotes any accessed pages of slower memories that were already in the youngest generation.
+	fn age(&mut self, cur_time: u64) {
+		let next_aging_time = *self
+			.next_aging_time
+			.get_or_insert(cur_time + self.config.aging_interval);
+		if cur_time < next_aging_time {
+			return;
+		}
+		self.next_aging_time = Some(cur_time + self.config.aging_interval);
+		self.agings += 1;
+
+		let mut promote_pages = vec![];
+		let mem_idxs = self
+			.tiered
+			.memories()
+			.iter()
+			.map(|(mem_idx, _)| mem_idx)
+			.collect::<Vec<_>>();
+		for mem_idx in mem_idxs {
+			let is_slow = !self.tiered.memories().promotion_targets(mem_idx).is_empty();
+			let generations = self.generations.entry(mem_idx).or_default();
+			let prev_max_seq = generations.max_seq();
+			let max_seq = generations.push_youngest();
+
+			for page_ptr in self.tiered.mem_pages(mem_idx) {
+				let page = self.pages.get_mut(&page_ptr).expect("Page wasn't mapped");
+				if !page.accessed {
+					continue;
+				}
+
+				if is_slow && page.seq == prev_max_seq {
+					promote_pages.push((page_ptr, mem_idx));
+				}
+				generations.move_page(page_ptr, page.seq, max_seq);
+				page.seq = max_seq;
+				page.accessed = false;
+			}
+
+			// Note: Pages of the merged generation must be moved to the new oldest
+			while generations.len() > self.config.generations {
+				let (merged_pages, min_seq) = generations.merge_oldest();
+				for page_ptr in merged_pages {
+					self.pages.get_mut(&page_ptr).expect("Page wasn't mapped").seq = min_seq;
+				}
+			}
+
+			self.tiered.set_generation_sizes(mem_idx, generations.sizes());
+		}
+
+		for (page_ptr, mem_idx) in promote_pages {
+			if let Err(err) = self.promote_page(cur_time, page_ptr, mem_idx) {
+				tracing::trace!(?page_ptr, ?err, "Unable to promote page");
+			}
+		}
+	}
+
+	/// Promotes a page from memory `mem_idx` to the nearest promotion target,
+	/// demoting from it's oldest generation first if it's full.
+	fn promote_page(&mut self, cur_time: u64, page_ptr: PagePtr, mem_idx: MemIdx) -> Result<(), anyhow::Error> {
+		let dst_mem_idx = *self
+			.tiered
+			.memories()
+			.promotion_targets(mem_idx)
+			.first()
+			.context("Memory has no promotion targets")?;
+		if self.tiered.memories().get(dst_mem_idx).is_full() {
+			self.demote_oldest_page(cur_time, dst_mem_idx)
+				.context("Unable to make room in promotion target")?;
+		}
+
+		self.migrate_page(cur_time, page_ptr, mem_idx, dst_mem_idx)
+	}
+
+	/// Demotes a page from the oldest generation of memory `mem_idx` to the nearest demotion target with space.
+	///
+	/// Pages accessed since the last scan are moved to the youngest generation instead.
+	fn demote_oldest_page(&mut self, cur_time: u64, mem_idx: MemIdx) -> Result<(), anyhow::Error> {
+		let dst_mem_idx = self
+			.tiered
+			.memories()
+			.demotion_targets(mem_idx)
+			.iter()
+			.copied()
+			.find(|&dst_mem_idx| !self.tiered.memories().get(dst_mem_idx).is_full())
+			.context("All demotion targets were full")?;
+
+		let generations = self.generations.entry(mem_idx).or_default();
+		let page_ptr = loop {
+			let (page_ptr, seq) = generations.oldest_page().context("Memory had no pages")?;
+			let page = self.pages.get_mut(&page_ptr).expect("Page wasn't mapped");
+			match page.accessed {
+				true => {
+					let max_seq = generations.max_seq();
+					generations.move_page(page_ptr, seq, max_seq);
+					page.seq = max_seq;
+					page.accessed = false;
+				},
+				false => break page_ptr,
+			}
+		};
+
+		self.migrate_page(cur_time, page_ptr, mem_idx, dst_mem_idx)
+	}
+
+	/// Migrates a page from memory `src_mem_idx` to the youngest generation of `dst_mem_idx`
+	fn migrate_page(
+		&mut self,
+		cur_time: u64,
+		page_ptr: PagePtr,
+		src_mem_idx: MemIdx,
+		dst_mem_idx: MemIdx,
+	) -> Result<(), anyhow::Error> {
+		self.tiered.migrate_page(cur_time, page_ptr, dst_mem_idx)?;
+
+		let page = self.pages.get_mut(&page_ptr).expect("Page wasn't mapped");
+		self.generations
+			.entry(src_mem_idx)
+			.or_default()
+			.remove_page(page_ptr, page.seq);
+		page.seq = self
+			.generations
+			.entry(dst_mem_idx)
+			.or_default()
+			.insert_youngest(page_ptr);
+
+		Ok(())
+	}
+}
+
+impl sim::Classifier for Mglru {
+	fn register_trace(&mut self, trace: sim::Trace) {
+		self.tiered.register_trace_record(trace.record.time);
+	}
+
+	fn handle_trace(&mut self, trace: sim::Trace) -> Result<(), anyhow::Error> {
+		tracing::trace!(?trace, "Received trace");
+		let page_ptr = PagePtr::new(trace.record.addr);
+		let cur_time = trace.record.time;
+
+		self.age(cur_time);
+
+		// Map the page to the youngest generation if it doesn't exist
+		let access_mem = match self.tiered.page_mem(page_ptr) {
+			Some(mem_idx) => statistics::AccessMem::Resided(mem_idx),
+			None => {
+				let mem_idx = match self.tiered.map_page_first_fit(cur_time, page_ptr) {
+					Ok(mem_idx) => mem_idx,
+					Err(err) =>
+						return self
+							.tiered
+							.handle_oom(cur_time, page_ptr, err)
+							.context("Unable to map page"),
+				};
+				let seq = self.generations.entry(mem_idx).or_default().insert_youngest(page_ptr);
+				self.pages.insert(page_ptr, Page { seq, accessed: false });
+				statistics::AccessMem::Mapped(mem_idx)
+			},
+		};
+
+		// Then set it's accessed bit, for the next scan
+		self.pages.get_mut(&page_ptr).expect("Page wasn't mapped").accessed = true;
+		self.tiered
+			.access(trace.record, access_mem, FemtoDuration::ZERO, 0, 0, false);
+
+		Ok(())
+	}
+
+	fn finish(&mut self) {
+		self.tiered.finish();
+	}
+
+	fn add_observer(&mut self, observer: impl Observer + 'static) {
+		self.tiered.add_observer(observer);
+	}
+
+	fn fmt_debug(&mut self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+		// Note: Start with a newline, since we're a multi-line output
+		f.pad("\n")?;
+
+		self.tiered.fmt_debug(f)?;
+		for (mem_idx, generations) in &self.generations {
+			writeln!(
+				f,
+				"Generations ({mem_idx:?}): {:?} (oldest to youngest)",
+				generations.sizes()
+			)?;
+		}
+
+		writeln!(f, "Agings: {}", self.agings)
+	}
+
+	fn debug_stats(&mut self) -> serde_json::Value {
+		let mut stats = self.tiered.debug_stats();
+		stats["generations"] = self
+			.generations
+			.iter()
+			.map(|(mem_idx, generations)| {
+				serde_json::json!({
+					"idx": mem_idx.to_usize(),
+					"sizes": generations.sizes(),
+				})
+			})
+			.collect();
+		stats["agings"] = serde_json::Value::from(self.agings);
+		stats
+	}
+}
+
+/// Configuration
+#[derive(Clone, Debug)]
+pub struct Config {
+	/// Max generations per memory
+	pub generations: usize,
+
+	/// Period between each aging (in trace time)
+	pub aging_interval: u64,
+}
+
+/// Page
+#[derive(Clone, Copy, Debug)]
+struct Page {
+	/// Sequence number of it's generation
+	seq: u64,
+
+	/// Accessed bit
+	accessed: bool,
+}
+
+/// Generations of a memory
+#[derive(Clone, Debug)]
+struct Generations {
+	/// Sequence number of the oldest generation
+	min_seq: u64,
+
+	/// Pages of each generation, from the oldest to the youngest
+	pages: VecDeque<BTreeSet<PagePtr>>,
+}
+
+impl Generations {
+	/// Returns the number of generations
+	fn len(&self) -> usize {
+		self.pages.len()
+	}
+
+	/// Returns the sequence number of the youngest generation
+	fn max_seq(&self) -> u64 {
+		self.min_seq + self.pages.len() as u64 - 1
+	}
+
+	/// Returns the pages of generation `seq`
+	fn generation_mut(&mut self, seq: u64) -> &mut BTreeSet<PagePtr> {
+		let idx = usize::try_from(seq - self.min_seq).expect("Generation index didn't fit into a `usize`");
+		&mut self.pages[idx]
+	}
+
+	/// Creates a new youngest generation and returns it's sequence number
+	fn push_youngest(&mut self) -> u64 {
+		self.pages.push_back(BTreeSet::new());
+		self.max_seq()
+	}
+
+	/// Inserts a page into the youngest generation and returns it's sequence number
+	fn insert_youngest(&mut self, page_ptr: PagePtr) -> u64 {
+		let max_seq = self.max_seq();
+		self.generation_mut(max_seq).insert(page_ptr);
+		max_seq
+	}
+
+	/// Removes a page from generation `seq`, dropping any empty oldest generations
+	fn remove_page(&mut self, page_ptr: PagePtr, seq: u64) {
+		self.generation_mut(seq).remove(&page_ptr);
+		while self.pages.len() > 1 && self.pages.front().is_some_and(BTreeSet::is_empty) {
+			self.pages.pop_front();
+			self.min_seq += 1;
+		}
+	}
+
+	/// Moves a page from generation `src_seq` to the youngest generation, `dst_seq`
+	fn move_page(&mut self, page_ptr: PagePtr, src_seq: u64, dst_seq: u64) {
+		self.remove_page(page_ptr, src_seq);
+		self.generation_mut(dst_seq).insert(page_ptr);
+	}
+
+	/// Returns the first page of the oldest non-empty generation, along with it's sequence number
+	fn oldest_page(&self) -> Option<(PagePtr, u64)> {
+		self.pages
+			.iter()
+			.zip(self.min_seq..)
+			.find_map(|(pages, seq)| pages.first().map(|&page_ptr| (page_ptr, seq)))
+	}
+
+	/// Merges the oldest generation into the next, returning it's pages and the new oldest sequence number.
+	///
+	/// # Panics
+	/// Panics if there are less than 2 generations.
+	fn merge_oldest(&mut self) -> (BTreeSet<PagePtr>, u64) {
+		assert!(self.pages.len() >= 2, "Cannot merge the only generation");
+		let oldest = self.pages.pop_front().expect("Just checked");
+		self.min_seq += 1;
+		self.pages
+			.front_mut()
+			.expect("Just checked")
+			.extend(oldest.iter().copied());
+
+		(oldest, self.min_seq)
+	}
+
+	/// Returns the number of pages of each generation, from the oldest to the youngest
+	fn sizes(&self) -> Vec<usize> {
+		self.pages.iter().map(BTreeSet::len).collect()
+	}
+}
+
+impl Default for Generations {
+	fn default() -> Self {
+		Self {
+			min_seq: 0,
+			pages:   VecDeque::from([BTreeSet::new()]),
+		}
+	}
+}
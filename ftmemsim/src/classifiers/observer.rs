@@ -0,0 +1,133 @@
+//! Observers
+
+// Imports
+use {
+	super::hemem::{
+		memories::MemIdx,
+		statistics::{Access, Epoch, PageMigration},
+		PagePtr,
+		Statistics,
+	},
+	std::fmt,
+};
+
+/// Observer of the lifecycle of a classifier.
+///
+/// Allows writing custom analyses without modifying the classifier.
+/// All methods do nothing by default, so observers only need to implement the events they're interested in.
+pub trait Observer: Send {
+	/// Called after each access, once the classifier has handled it
+	fn on_access(&mut self, access: &Access) {
+		let _ = access;
+	}
+
+	/// Called when a page is first mapped to memory `mem_idx`
+	fn on_map(&mut self, time: u64, page_ptr: PagePtr, mem_idx: MemIdx) {
+		let _ = (time, page_ptr, mem_idx);
+	}
+
+	/// Called when a page is migrated between memories
+	fn on_migrate(&mut self, page_ptr: PagePtr, migration: &PageMigration) {
+		let _ = (page_ptr, migration);
+	}
+
+	/// Called when all pages are cooled
+	fn on_cool_all(&mut self, time: u64) {
+		let _ = time;
+	}
+
+	/// Called when an epoch of the statistics is finished
+	fn on_epoch(&mut self, epoch: &Epoch) {
+		let _ = epoch;
+	}
+}
+
+impl<O: Observer + ?Sized> Observer for Box<O> {
+	fn on_access(&mut self, access: &Access) {
+		(**self).on_access(access);
+	}
+
+	fn on_map(&mut self, time: u64, page_ptr: PagePtr, mem_idx: MemIdx) {
+		(**self).on_map(time, page_ptr, mem_idx);
+	}
+
+	fn on_migrate(&mut self, page_ptr: PagePtr, migration: &PageMigration) {
+		(**self).on_migrate(page_ptr, migration);
+	}
+
+	fn on_cool_all(&mut self, time: u64) {
+		(**self).on_cool_all(time);
+	}
+
+	fn on_epoch(&mut self, epoch: &Epoch) {
+		(**self).on_epoch(epoch);
+	}
+}
+
+/// Observers of a classifier
+#[derive(Default)]
+pub struct Observers {
+	/// Observers
+	observers: Vec<Box<dyn Observer>>,
+
+	/// Number of finished epochs the observers have been notified of
+	notified_epochs: usize,
+}
+
+impl Observers {
+	/// Creates a new, empty, set of observers
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds an observer
+	pub fn add(&mut self, observer: impl Observer + 'static) {
+		self.observers.push(Box::new(observer));
+	}
+
+	/// Notifies all observers of an access
+	pub fn on_access(&mut self, access: &Access) {
+		for observer in &mut self.observers {
+			observer.on_access(access);
+		}
+	}
+
+	/// Notifies all observers of a page migration.
+	///
+	/// Migrations without a previous memory are notified as a mapping.
+	pub fn on_page_migration(&mut self, page_ptr: PagePtr, page_migration: &PageMigration) {
+		for observer in &mut self.observers {
+			match page_migration.prev_mem_idx {
+				Some(_) => observer.on_migrate(page_ptr, page_migration),
+				None => observer.on_map(page_migration.time, page_ptr, page_migration.cur_mem_idx),
+			}
+		}
+	}
+
+	/// Notifies all observers that all pages were cooled
+	pub fn on_cool_all(&mut self, time: u64) {
+		for observer in &mut self.observers {
+			observer.on_cool_all(time);
+		}
+	}
+
+	/// Notifies all observers of any epochs of `statistics` finished since the last notification
+	pub fn notify_finished_epochs(&mut self, statistics: &Statistics) {
+		let epochs = statistics.finished_epochs();
+		for epoch in &epochs[self.notified_epochs..] {
+			for observer in &mut self.observers {
+				observer.on_epoch(epoch);
+			}
+		}
+		self.notified_epochs = epochs.len();
+	}
+}
+
+impl fmt::Debug for Observers {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Observers")
+			.field("observers", &self.observers.len())
+			.field("notified_epochs", &self.notified_epochs)
+			.finish()
+	}
+}
@@ -0,0 +1,205 @@
+//! Static placement classifier
+//!
+//! Baselines that place each page in a memory when it's first accessed and
+//! never migrate it afterwards.
+
+// Imports
+use {
+	super::{
+		hemem::{memories::MemIdx, statistics, PagePtr},
+		observer::Observer,
+		tiered::TieredMemory,
+	},
+	crate::sim,
+	anyhow::Context,
+	ftmemsim_util::FemtoDuration,
+	rand::{rngs::StdRng, Rng, SeedableRng},
+	std::fmt,
+};
+
+/// Static placement classifier
+#[derive(Debug)]
+pub struct Placement {
+	/// Policy
+	policy: Policy,
+
+	/// Tiered memory
+	tiered: TieredMemory,
+}
+
+impl Placement {
+	/// Creates a static placement classifier
+	///
+	/// # Errors
+	/// Returns an error if an interleave ratio doesn't have a weight per memory, or if they're all 0.
+	pub fn new(config: Config, tiered: TieredMemory) -> Result<Self, anyhow::Error> {
+		let policy = match config {
+			Config::FirstTouch => Policy::FirstTouch,
+			Config::Interleave { ratio } => {
+				anyhow::ensure!(
+					ratio.len() == tiered.memories().iter().count(),
+					"Interleave ratio must have a weight per memory"
+				);
+				anyhow::ensure!(
+					ratio.iter().any(|&weight| weight != 0),
+					"Interleave ratio must not be all 0"
+				);
+
+				Policy::Interleave {
+					ratio,
+					cur_idx: 0,
+					cur_placed: 0,
+				}
+			},
+			Config::Random { seed } => Policy::Random(Box::new(StdRng::seed_from_u64(seed))),
+		};
+
+		Ok(Self { policy, tiered })
+	}
+
+	/// Returns the statistics
+	pub fn statistics(&self) -> &statistics::Statistics {
+		self.tiered.statistics()
+	}
+
+	/// Maps a page according to the policy, returning the memory it was mapped to.
+	///
+	/// If the chosen memory is full, falls back to the first fit.
+	fn map_page(&mut self, cur_time: u64, page_ptr: PagePtr) -> Result<MemIdx, anyhow::Error> {
+		let mem_idxs = self
+			.tiered
+			.memories()
+			.iter()
+			.map(|(mem_idx, _)| mem_idx)
+			.collect::<Vec<_>>();
+		let mem_idx = match &mut self.policy {
+			Policy::FirstTouch => None,
+
+			// Note: We skip any weights of 0 to avoid stalling on them
+			Policy::Interleave {
+				ratio,
+				cur_idx,
+				cur_placed,
+			} => {
+				while *cur_placed >= ratio[*cur_idx] {
+					*cur_idx = (*cur_idx + 1) % ratio.len();
+					*cur_placed = 0;
+				}
+				*cur_placed += 1;
+				Some(mem_idxs[*cur_idx])
+			},
+
+			// Note: We weigh each memory by it's capacity, so that pages are spread uniformly
+			Policy::Random(rng) => {
+				let total_capacity = self
+					.tiered
+					.memories()
+					.iter()
+					.map(|(_, mem)| mem.page_capacity())
+					.sum::<usize>();
+				let mut page_idx = rng.gen_range(0..total_capacity.max(1));
+				self.tiered.memories().iter().find_map(|(mem_idx, mem)| {
+					match page_idx.checked_sub(mem.page_capacity()) {
+						Some(next_page_idx) => {
+							page_idx = next_page_idx;
+							None
+						},
+						None => Some(mem_idx),
+					}
+				})
+			},
+		};
+
+		match mem_idx {
+			Some(mem_idx) if !self.tiered.memories().get(mem_idx).is_full() => {
+				self.tiered.map_page(cur_time, page_ptr, mem_idx)?;
+				Ok(mem_idx)
+			},
+			_ => self.tiered.map_page_first_fit(cur_time, page_ptr),
+		}
+	}
+}
+
+impl sim::Classifier for Placement {
+	fn register_trace(&mut self, trace: sim::Trace) {
+		self.tiered.register_trace_record(trace.record.time);
+	}
+
+	fn handle_trace(&mut self, trace: sim::Trace) -> Result<(), anyhow::Error> {
+		tracing::trace!(?trace, "Received trace");
+		let page_ptr = PagePtr::new(trace.record.addr);
+
+		let access_mem = match self.tiered.page_mem(page_ptr) {
+			Some(mem_idx) => statistics::AccessMem::Resided(mem_idx),
+			None => {
+				let mem_idx = match self.map_page(trace.record.time, page_ptr) {
+					Ok(mem_idx) => mem_idx,
+					Err(err) =>
+						return self
+							.tiered
+							.handle_oom(trace.record.time, page_ptr, err)
+							.context("Unable to map page"),
+				};
+				statistics::AccessMem::Mapped(mem_idx)
+			},
+		};
+		self.tiered
+			.access(trace.record, access_mem, FemtoDuration::ZERO, 0, 0, false);
+
+		Ok(())
+	}
+
+	fn finish(&mut self) {
+		self.tiered.finish();
+	}
+
+	fn add_observer(&mut self, observer: impl Observer + 'static) {
+		self.tiered.add_observer(observer);
+	}
+
+	fn fmt_debug(&mut self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+		// Note: Start with a newline, since we're a multi-line output
+		f.pad("\n")?;
+
+		self.tiered.fmt_debug(f)
+	}
+
+	fn debug_stats(&mut self) -> serde_json::Value {
+		self.tiered.debug_stats()
+	}
+}
+
+/// Configuration
+#[derive(Clone, Debug)]
+pub enum Config {
+	/// Maps each page to the fastest memory with space
+	FirstTouch,
+
+	/// Maps pages round-robin across the memories, `ratio[n]` pages at a time to memory `n`
+	Interleave { ratio: Vec<usize> },
+
+	/// Maps each page to a random memory, weighted by their capacity
+	Random { seed: u64 },
+}
+
+/// Policy
+#[derive(Debug)]
+enum Policy {
+	/// First touch
+	FirstTouch,
+
+	/// Interleave
+	Interleave {
+		/// Pages to map to each memory at a time
+		ratio: Vec<usize>,
+
+		/// Index of the current memory
+		cur_idx: usize,
+
+		/// Pages mapped to the current memory
+		cur_placed: usize,
+	},
+
+	/// Random
+	Random(Box<StdRng>),
+}
@@ -0,0 +1,391 @@
+//! Tiered memory
+//!
+//! Shared by the classifiers other than hemem to keep track of which memory
+//! each page is in, to account the latency and statistics of each access
+//! and migration the same way hemem does, and to notify any observers of them.
+
+// Imports
+use {
+	super::{
+		hemem::{
+			memories::{self, MemIdx},
+			statistics::{self, Statistics},
+			Memories,
+			OomBehavior,
+			Page,
+			PagePtr,
+			PageTable,
+			Thp,
+			Tlb,
+		},
+		observer::{Observer, Observers},
+	},
+	crate::pin_trace,
+	ftmemsim_util::FemtoDuration,
+	std::fmt,
+};
+
+/// Tiered memory
+#[derive(Debug)]
+pub struct TieredMemory {
+	/// Memories
+	memories: Memories,
+
+	/// Pages.
+	///
+	/// The classifiers keep their own hotness, so we only track the memory of each page.
+	pages: PageTable<()>,
+
+	/// Statistics
+	statistics: Statistics,
+
+	/// Behavior when a page can't be mapped
+	oom_behavior: OomBehavior,
+
+	/// Window over which bandwidth is accounted (in trace time)
+	bandwidth_window: u64,
+
+	/// TLB, if any
+	tlb: Option<Tlb>,
+
+	/// Transparent huge pages, if any
+	thp: Option<Thp>,
+
+	/// Observers
+	observers: Observers,
+}
+
+impl TieredMemory {
+	/// Creates a new tiered memory.
+	///
+	/// See [`Statistics::new`] for `epoch_length` and `access_log`.
+	///
+	/// If `tlb` is `Some`, each access is first translated by it, and it's misses
+	/// pay the page walk latency.
+	///
+	/// If `thp` is `Some`, each access may split or collapse the huge page it's in,
+	/// paying it's latency, and it decides the size of the page each access is translated with.
+	///
+	/// # Errors
+	/// Returns an error if `oom_behavior` is [`OomBehavior::Swap`], since there's no swap memory,
+	/// or if `tlb` has no entries for the page sizes of `thp`.
+	pub fn new(
+		memories: Memories,
+		epoch_length: Option<statistics::EpochLength>,
+		access_log: bool,
+		oom_behavior: OomBehavior,
+		bandwidth_window: u64,
+		tlb: Option<Tlb>,
+		thp: Option<Thp>,
+	) -> Result<Self, anyhow::Error> {
+		anyhow::ensure!(
+			!matches!(oom_behavior, OomBehavior::Swap),
+			"Tiered memory can't swap when out of memory"
+		);
+		if let (Some(tlb), Some(_)) = (&tlb, &thp) {
+			anyhow::ensure!(
+				tlb.has_page_size(Thp::PAGE_SIZE) && tlb.has_page_size(Thp::HUGE_PAGE_SIZE),
+				"TLB must have entries for both 4KiB and 2MiB pages when using transparent huge pages"
+			);
+		}
+
+		Ok(Self {
+			memories,
+			pages: PageTable::new(),
+			statistics: Statistics::new(epoch_length, access_log),
+			oom_behavior,
+			bandwidth_window,
+			tlb,
+			thp,
+			observers: Observers::new(),
+		})
+	}
+
+	/// Adds an observer
+	pub fn add_observer(&mut self, observer: impl Observer + 'static) {
+		self.observers.add(observer);
+	}
+
+	/// Returns the memories
+	pub fn memories(&self) -> &Memories {
+		&self.memories
+	}
+
+	/// Returns the statistics
+	pub fn statistics(&self) -> &Statistics {
+		&self.statistics
+	}
+
+	/// Returns the memory of a page, if it's mapped
+	pub fn page_mem(&self, page_ptr: PagePtr) -> Option<MemIdx> {
+		self.pages.mem_idx(page_ptr)
+	}
+
+	/// Returns all pages in memory `mem_idx`, by address
+	pub fn mem_pages(&self, mem_idx: MemIdx) -> Vec<PagePtr> {
+		let mut page_ptrs = self.pages.mem_pages(mem_idx).collect::<Vec<_>>();
+		page_ptrs.sort_unstable();
+		page_ptrs
+	}
+
+	/// Returns the next `count` pages in memory `mem_idx` after `cursor`, by address.
+	///
+	/// Wraps around to the first page once it reaches the last.
+	pub fn mem_pages_after(&self, mem_idx: MemIdx, cursor: Option<PagePtr>, count: usize) -> Vec<PagePtr> {
+		// Note: Pages up to the cursor are only reached after wrapping around, so they sort last
+		let mut page_ptrs = self
+			.pages
+			.mem_pages(mem_idx)
+			.map(|page_ptr| (cursor.is_some_and(|cursor| page_ptr <= cursor), page_ptr))
+			.collect::<Vec<_>>();
+		if count < page_ptrs.len() {
+			page_ptrs.select_nth_unstable(count);
+			page_ptrs.truncate(count);
+		}
+		page_ptrs.sort_unstable();
+
+		page_ptrs.into_iter().map(|(_, page_ptr)| page_ptr).collect()
+	}
+
+	/// Maps a page to memory `mem_idx`
+	///
+	/// # Errors
+	/// Returns an error if the memory is full.
+	///
+	/// # Panics
+	/// Panics if the page is already mapped.
+	pub fn map_page(&mut self, cur_time: u64, page_ptr: PagePtr, mem_idx: MemIdx) -> Result<(), anyhow::Error> {
+		assert!(!self.pages.contains(page_ptr), "Page is already mapped: {page_ptr:?}");
+
+		self.memories.get_mut(mem_idx).reserve_page()?;
+		self.pages
+			.insert(Page::new(page_ptr, mem_idx, ()))
+			.expect("Page was already mapped");
+		self.register_page_migration(page_ptr, statistics::PageMigration {
+			time:         cur_time,
+			prev_mem_idx: None,
+			cur_mem_idx:  mem_idx,
+			kind:         None,
+		});
+
+		Ok(())
+	}
+
+	/// Maps a page to the fastest memory with space and returns it.
+	///
+	/// # Errors
+	/// Returns an error if all memories are full.
+	pub fn map_page_first_fit(&mut self, cur_time: u64, page_ptr: PagePtr) -> Result<MemIdx, anyhow::Error> {
+		let mem_idx = self
+			.memories
+			.iter()
+			.find(|(_, mem)| !mem.is_full())
+			.map(|(mem_idx, _)| mem_idx);
+		let Some(mem_idx) = mem_idx else {
+			anyhow::bail!("All memories were full");
+		};
+
+		self.map_page(cur_time, page_ptr, mem_idx)?;
+		Ok(mem_idx)
+	}
+
+	/// Handles being unable to map a page at `cur_time` with `err`.
+	///
+	/// If we're dropping accesses when out of memory, registers the access as dropped
+	/// and returns `Ok`, so the caller may skip it. Otherwise returns `err`.
+	pub fn handle_oom(&mut self, cur_time: u64, page_ptr: PagePtr, err: anyhow::Error) -> Result<(), anyhow::Error> {
+		match self.oom_behavior {
+			OomBehavior::Drop => {
+				tracing::trace!(?page_ptr, ?err, "Unable to map page, dropping access");
+				self.statistics.register_dropped_access(cur_time);
+				self.observers.notify_finished_epochs(&self.statistics);
+				Ok(())
+			},
+			OomBehavior::Error | OomBehavior::Swap => Err(err),
+		}
+	}
+
+	/// Migrates a page to memory `dst_mem_idx`, registering the traffic on both memories.
+	///
+	/// # Errors
+	/// Returns an error if the destination memory is full.
+	///
+	/// # Panics
+	/// Panics if the page isn't mapped.
+	pub fn migrate_page(&mut self, cur_time: u64, page_ptr: PagePtr, dst_mem_idx: MemIdx) -> Result<(), anyhow::Error> {
+		let src_mem_idx = self.page_mem(page_ptr).expect("Page wasn't mapped");
+		self.memories.migrate_page(src_mem_idx, dst_mem_idx)?;
+
+		self.pages.move_mem(page_ptr, dst_mem_idx);
+
+		self.memories.get_mut(src_mem_idx).register_traffic(
+			cur_time,
+			self.bandwidth_window,
+			memories::TrafficKind::Read,
+			memories::PAGE_BYTES,
+		);
+		self.memories.get_mut(dst_mem_idx).register_traffic(
+			cur_time,
+			self.bandwidth_window,
+			memories::TrafficKind::Write,
+			memories::PAGE_BYTES,
+		);
+		self.register_page_migration(page_ptr, statistics::PageMigration {
+			time:         cur_time,
+			prev_mem_idx: Some(src_mem_idx),
+			cur_mem_idx:  dst_mem_idx,
+			kind:         self.memories.migration_kind(src_mem_idx, dst_mem_idx),
+		});
+
+		Ok(())
+	}
+
+	/// Sets the number of pages of each generation of memory `mem_idx`
+	pub fn set_generation_sizes(&mut self, mem_idx: MemIdx, generation_sizes: Vec<usize>) {
+		self.statistics.set_generation_sizes(mem_idx, generation_sizes);
+	}
+
+	/// Registers a change of the hot threshold
+	pub fn register_hot_threshold(&mut self, hot_threshold: statistics::HotThreshold) {
+		self.statistics.register_hot_threshold(hot_threshold);
+	}
+
+	/// Registers an access from `record`, served by `access_mem`.
+	///
+	/// The access pays the latency of the memory, any queueing delay, the fault
+	/// latency if the page was just mapped, the page walk latency if it missed the tlb,
+	/// the latency of any huge page split or collapse it caused, and `extra_latency`.
+	pub fn access(
+		&mut self,
+		record: pin_trace::Record,
+		access_mem: statistics::AccessMem,
+		extra_latency: FemtoDuration,
+		prev_temperature: usize,
+		cur_temperature: usize,
+		caused_cooling: bool,
+	) {
+		// Track the access's region, if we have transparent huge pages
+		let thp_event = self.thp.as_mut().and_then(|thp| thp.access(record.time, record.addr));
+		let thp_latency = thp_event
+			.as_ref()
+			.map_or(FemtoDuration::ZERO, |thp_event| thp_event.cost);
+		if let Some(thp_event) = thp_event {
+			tracing::trace!(?thp_event, "Huge page split or collapsed");
+			self.statistics.register_thp_event(thp_event);
+		}
+
+		// Translate the access, if we have a tlb
+		let page_size = self.thp.as_ref().map(|thp| thp.page_size(record.addr));
+		let tlb_hit = self.tlb.as_mut().map(|tlb| {
+			let page_size = page_size.unwrap_or_else(|| tlb.page_size());
+			tlb.access(record.addr, page_size)
+		});
+		let page_walk_latency = match (&self.tlb, tlb_hit) {
+			(Some(tlb), Some(false)) => tlb.page_walk_latency(),
+			_ => FemtoDuration::ZERO,
+		};
+
+		let (statistics::AccessMem::Mapped(mem_idx) |
+		statistics::AccessMem::Resided(mem_idx) |
+		statistics::AccessMem::SwappedIn(mem_idx)) = access_mem;
+		let mem = self.memories.get_mut(mem_idx);
+		let latencies = mem.latencies();
+		let (access_latency, traffic_kind, kind) = match record.kind {
+			pin_trace::RecordAccessKind::Read => (
+				latencies.read,
+				memories::TrafficKind::Read,
+				statistics::AccessKind::Read,
+			),
+			pin_trace::RecordAccessKind::Write => (
+				latencies.write,
+				memories::TrafficKind::Write,
+				statistics::AccessKind::Write,
+			),
+		};
+		let queueing_delay =
+			mem.register_traffic(record.time, self.bandwidth_window, traffic_kind, memories::ACCESS_BYTES);
+		let latency = match access_mem {
+			statistics::AccessMem::Mapped(_) | statistics::AccessMem::SwappedIn(_) =>
+				access_latency + queueing_delay + latencies.fault,
+			statistics::AccessMem::Resided(_) => access_latency + queueing_delay,
+		} + page_walk_latency +
+			thp_latency +
+			extra_latency;
+
+		let access = statistics::Access {
+			time: record.time,
+			page_ptr: PagePtr::new(record.addr),
+			kind,
+			mem: access_mem,
+			prev_temperature,
+			cur_temperature,
+			caused_cooling,
+			latency,
+			queueing_delay,
+			tlb_hit,
+		};
+		self.statistics.register_access(access);
+		self.observers.on_access(&access);
+		self.observers.notify_finished_epochs(&self.statistics);
+	}
+
+	/// Registers a trace record at `cur_time`.
+	///
+	/// See [`Statistics::register_trace_record`].
+	pub fn register_trace_record(&mut self, cur_time: u64) {
+		self.statistics.register_trace_record(cur_time);
+		self.observers.notify_finished_epochs(&self.statistics);
+	}
+
+	/// Finishes the statistics, once the run is finished
+	pub fn finish(&mut self) {
+		self.statistics.finish();
+		self.observers.notify_finished_epochs(&self.statistics);
+	}
+
+	/// Notifies all observers that all pages were cooled
+	pub fn register_cool_all(&mut self, cur_time: u64) {
+		self.observers.on_cool_all(cur_time);
+	}
+
+	/// Registers a page migration on the statistics and notifies all observers
+	fn register_page_migration(&mut self, page_ptr: PagePtr, page_migration: statistics::PageMigration) {
+		self.observers.on_page_migration(page_ptr, &page_migration);
+		self.statistics.register_page_migration(page_ptr, page_migration);
+		self.observers.notify_finished_epochs(&self.statistics);
+	}
+
+	/// Formats the occupancy of each memory to `f`.
+	pub fn fmt_debug(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+		for (mem_idx, memory) in self.memories.iter() {
+			let name = memory.name();
+			let len = memory.page_len();
+			let capacity = memory.page_capacity();
+			let occupancy_percentage = 100.0 * (len as f64 / capacity as f64);
+			writeln!(
+				f,
+				"Memory {name} ({mem_idx:?}): {len} / {capacity} ({occupancy_percentage:.2}%)"
+			)?;
+		}
+
+		Ok(())
+	}
+
+	/// Returns machine-readable debug stats of each memory
+	pub fn debug_stats(&self) -> serde_json::Value {
+		let memories = self
+			.memories
+			.iter()
+			.map(|(mem_idx, memory)| {
+				serde_json::json!({
+					"idx": mem_idx.to_usize(),
+					"name": memory.name(),
+					"page_len": memory.page_len(),
+					"page_capacity": memory.page_capacity(),
+				})
+			})
+			.collect::<Vec<_>>();
+
+		serde_json::json!({ "memories": memories })
+	}
+}
@@ -0,0 +1,443 @@
+//! TPP (Transparent Page Placement) classifier
+//!
+//! Models the kernel's TPP tiering: New pages are allocated in the fastest memory
+//! with space, and memories with demotion targets are proactively reclaimed once
+//! their free pages drop below a low watermark, demoting the least recently used
+//! pages of their inactive list until they reach a high watermark.
+//!
+//! Pages in memories with promotion targets are periodically scanned to arm a
+//! NUMA hint fault on their next access, which only promotes them if they're on
+//! the active list.
+//!
+//! Note: We see every access, but the kernel only sees the accessed bit of each page,
+//!       so accesses only mark their page as referenced. Pages are activated when
+//!       referenced twice by the reclaim and hint faults, like `mark_page_accessed`.
+
+// Imports
+use {
+	super::{
+		hemem::{memories::MemIdx, statistics, PagePtr},
+		lru_list::LruList,
+		observer::Observer,
+		tiered::TieredMemory,
+	},
+	crate::sim,
+	anyhow::Context,
+	ftmemsim_util::FemtoDuration,
+	std::{collections::BTreeMap, fmt},
+};
+
+/// TPP classifier
+#[derive(Debug)]
+pub struct Tpp {
+	/// Config
+	config: Config,
+
+	/// Tiered memory
+	tiered: TieredMemory,
+
+	/// State of each page
+	pages: BTreeMap<PagePtr, PageState>,
+
+	/// LRU lists of each memory
+	lists: BTreeMap<MemIdx, Lists>,
+
+	/// Next time to scan for hint faults
+	next_scan_time: Option<u64>,
+
+	/// Last page scanned in each memory
+	scan_cursors: BTreeMap<MemIdx, PagePtr>,
+}
+
+impl Tpp {
+	/// Creates a TPP classifier
+	///
+	/// # Errors
+	/// Returns an error if the watermarks are invalid, or if the scan period is 0.
+	pub fn new(config: Config, tiered: TieredMemory) -> Result<Self, anyhow::Error> {
+		anyhow::ensure!(
+			(0.0..=1.0).contains(&config.low_watermark) && (0.0..=1.0).contains(&config.high_watermark),
+			"Watermarks must be between 0 and 1"
+		);
+		anyhow::ensure!(
+			config.low_watermark <= config.high_watermark,
+			"Low watermark must not be above the high watermark"
+		);
+		anyhow::ensure!(config.scan_period != 0, "Scan period must not be 0");
+
+		Ok(Self {
+			config,
+			tiered,
+			pages: BTreeMap::new(),
+			lists: BTreeMap::new(),
+			next_scan_time: None,
+			scan_cursors: BTreeMap::new(),
+		})
+	}
+
+	/// Returns the statistics
+	pub fn statistics(&self) -> &statistics::Statistics {
+		self.tiered.statistics()
+	}
+
+	/// Maps a page to the fastest memory with space and returns it, reclaiming it if necessary.
+	///
+	/// If all memories are full, reclaims them from fastest to slowest until one has space.
+	fn map_page(&mut self, cur_time: u64, page_ptr: PagePtr) -> Result<MemIdx, anyhow::Error> {
+		let mem_idx = match self.tiered.map_page_first_fit(cur_time, page_ptr) {
+			Ok(mem_idx) => mem_idx,
+			Err(err) => {
+				tracing::trace!(?page_ptr, ?err, "Unable to map page, reclaiming all memories");
+				let mem_idxs = self
+					.tiered
+					.memories()
+					.iter()
+					.map(|(mem_idx, _)| mem_idx)
+					.collect::<Vec<_>>();
+				for mem_idx in mem_idxs {
+					self.reclaim(cur_time, mem_idx);
+				}
+
+				self.tiered.map_page_first_fit(cur_time, page_ptr)?
+			},
+		};
+
+		self.pages.insert(page_ptr, PageState::default());
+		self.lists.entry(mem_idx).or_default().inactive.push_front(page_ptr);
+		self.reclaim_if_low(cur_time, mem_idx);
+
+		Ok(mem_idx)
+	}
+
+	/// Returns the free pages of memory `mem_idx` below which it's reclaimed and up to which it's reclaimed
+	fn watermarks(&self, mem_idx: MemIdx) -> (usize, usize) {
+		let capacity = self.tiered.memories().get(mem_idx).page_capacity() as f64;
+		(
+			(capacity * self.config.low_watermark).ceil() as usize,
+			(capacity * self.config.high_watermark).ceil() as usize,
+		)
+	}
+
+	/// Returns the free pages of memory `mem_idx`
+	fn free_pages(&self, mem_idx: MemIdx) -> usize {
+		let mem = self.tiered.memories().get(mem_idx);
+		mem.page_capacity().saturating_sub(mem.page_len())
+	}
+
+	/// Reclaims memory `mem_idx` if it's below the low watermark
+	fn reclaim_if_low(&mut self, cur_time: u64, mem_idx: MemIdx) {
+		let (low_watermark, _) = self.watermarks(mem_idx);
+		if self.free_pages(mem_idx) < low_watermark {
+			self.reclaim(cur_time, mem_idx);
+		}
+	}
+
+	/// Reclaims memory `mem_idx`, demoting pages until it reaches the high watermark.
+	///
+	/// Scans at most `reclaim_scan_pages` pages, and stops early if unable to demote any more.
+	fn reclaim(&mut self, cur_time: u64, mem_idx: MemIdx) {
+		if self.tiered.memories().demotion_targets(mem_idx).is_empty() {
+			return;
+		}
+
+		let (_, high_watermark) = self.watermarks(mem_idx);
+		for _ in 0..self.config.reclaim_scan_pages {
+			if self.free_pages(mem_idx) >= high_watermark.max(1) {
+				break;
+			}
+
+			// Keep the inactive list at least as large as the active list
+			let lists = self.lists.entry(mem_idx).or_default();
+			if lists.inactive.len() < lists.active.len() {
+				self.age_active(mem_idx);
+				continue;
+			}
+
+			// Then give referenced pages a second chance and demote the others
+			let Some(page_ptr) = lists.inactive.pop_back() else {
+				break;
+			};
+			let page = self.pages.get_mut(&page_ptr).expect("Page in list wasn't mapped");
+			if page.referenced {
+				page.active = true;
+				page.referenced = false;
+				lists.active.push_front(page_ptr);
+				continue;
+			}
+
+			match self.demote_page(cur_time, page_ptr, mem_idx) {
+				Ok(()) => (),
+				Err(err) => {
+					tracing::trace!(?page_ptr, ?mem_idx, ?err, "Unable to demote page, stopping reclaim");
+					self.lists.entry(mem_idx).or_default().inactive.push_back(page_ptr);
+					break;
+				},
+			}
+		}
+	}
+
+	/// Ages the least recently used page of the active list of memory `mem_idx`.
+	///
+	/// Referenced pages are rotated back to the front, while the others are deactivated.
+	fn age_active(&mut self, mem_idx: MemIdx) {
+		let lists = self.lists.entry(mem_idx).or_default();
+		let Some(page_ptr) = lists.active.pop_back() else {
+			return;
+		};
+
+		let page = self.pages.get_mut(&page_ptr).expect("Page in list wasn't mapped");
+		match page.referenced {
+			true => {
+				page.referenced = false;
+				lists.active.push_front(page_ptr);
+			},
+			false => {
+				page.active = false;
+				lists.inactive.push_front(page_ptr);
+			},
+		}
+	}
+
+	/// Demotes a page from memory `mem_idx` to the nearest demotion target with space
+	fn demote_page(&mut self, cur_time: u64, page_ptr: PagePtr, mem_idx: MemIdx) -> Result<(), anyhow::Error> {
+		let dst_mem_idx = self
+			.tiered
+			.memories()
+			.demotion_targets(mem_idx)
+			.iter()
+			.copied()
+			.find(|&dst_mem_idx| !self.tiered.memories().get(dst_mem_idx).is_full())
+			.context("All demotion targets were full")?;
+
+		self.tiered.migrate_page(cur_time, page_ptr, dst_mem_idx)?;
+		let page = self.pages.get_mut(&page_ptr).expect("Page wasn't mapped");
+		page.active = false;
+		self.lists.entry(dst_mem_idx).or_default().inactive.push_front(page_ptr);
+
+		Ok(())
+	}
+
+	/// Promotes an active page from memory `mem_idx` to the nearest promotion target,
+	/// reclaiming it first if it's full.
+	fn promote_page(&mut self, cur_time: u64, page_ptr: PagePtr, mem_idx: MemIdx) -> Result<(), anyhow::Error> {
+		let dst_mem_idx = *self
+			.tiered
+			.memories()
+			.promotion_targets(mem_idx)
+			.first()
+			.context("Memory has no promotion targets")?;
+		if self.tiered.memories().get(dst_mem_idx).is_full() {
+			self.reclaim(cur_time, dst_mem_idx);
+		}
+
+		self.tiered.migrate_page(cur_time, page_ptr, dst_mem_idx)?;
+		self.lists.entry(mem_idx).or_default().active.remove(page_ptr);
+		self.lists.entry(dst_mem_idx).or_default().active.push_front(page_ptr);
+		self.reclaim_if_low(cur_time, dst_mem_idx);
+
+		Ok(())
+	}
+
+	/// Marks a page as accessed, activating it if it was already referenced.
+	fn mark_page_accessed(&mut self, page_ptr: PagePtr, mem_idx: MemIdx) {
+		let page = self.pages.get_mut(&page_ptr).expect("Page wasn't mapped");
+		match (page.active, page.referenced) {
+			(false, true) => {
+				page.active = true;
+				page.referenced = false;
+				let lists = self.lists.entry(mem_idx).or_default();
+				lists.inactive.remove(page_ptr);
+				lists.active.push_front(page_ptr);
+			},
+			_ => page.referenced = true,
+		}
+	}
+
+	/// Scans for hint faults, if it's been long enough since the last scan.
+	///
+	/// Arms a hint fault on the next `scan_pages` pages, by address, of each memory with promotion targets.
+	fn scan(&mut self, cur_time: u64) {
+		let next_scan_time = *self.next_scan_time.get_or_insert(cur_time + self.config.scan_period);
+		if cur_time < next_scan_time {
+			return;
+		}
+		self.next_scan_time = Some(cur_time + self.config.scan_period);
+
+		let mem_idxs = self
+			.tiered
+			.memories()
+			.iter()
+			.map(|(mem_idx, _)| mem_idx)
+			.filter(|&mem_idx| !self.tiered.memories().promotion_targets(mem_idx).is_empty())
+			.collect::<Vec<_>>();
+		for mem_idx in mem_idxs {
+			let cursor = self.scan_cursors.get(&mem_idx).copied();
+			let page_ptrs = self.tiered.mem_pages_after(mem_idx, cursor, self.config.scan_pages);
+
+			for &page_ptr in &page_ptrs {
+				self.pages.get_mut(&page_ptr).expect("Page wasn't mapped").hint_fault = true;
+			}
+			if let Some(&page_ptr) = page_ptrs.last() {
+				self.scan_cursors.insert(mem_idx, page_ptr);
+			}
+		}
+	}
+}
+
+impl sim::Classifier for Tpp {
+	fn register_trace(&mut self, trace: sim::Trace) {
+		self.tiered.register_trace_record(trace.record.time);
+	}
+
+	fn handle_trace(&mut self, trace: sim::Trace) -> Result<(), anyhow::Error> {
+		tracing::trace!(?trace, "Received trace");
+		let page_ptr = PagePtr::new(trace.record.addr);
+		let cur_time = trace.record.time;
+
+		self.scan(cur_time);
+
+		// Map the page if it doesn't exist
+		let Some(mem_idx) = self.tiered.page_mem(page_ptr) else {
+			let mem_idx = match self.map_page(cur_time, page_ptr) {
+				Ok(mem_idx) => mem_idx,
+				Err(err) =>
+					return self
+						.tiered
+						.handle_oom(cur_time, page_ptr, err)
+						.context("Unable to map page"),
+			};
+			self.tiered.access(
+				trace.record,
+				statistics::AccessMem::Mapped(mem_idx),
+				FemtoDuration::ZERO,
+				0,
+				0,
+				false,
+			);
+			return Ok(());
+		};
+
+		// If we didn't hint fault, just mark it as referenced
+		let page = self.pages.get_mut(&page_ptr).expect("Page wasn't mapped");
+		let prev_temperature = page.temperature();
+		if !page.hint_fault {
+			page.referenced = true;
+			let cur_temperature = page.temperature();
+			self.tiered.access(
+				trace.record,
+				statistics::AccessMem::Resided(mem_idx),
+				FemtoDuration::ZERO,
+				prev_temperature,
+				cur_temperature,
+				false,
+			);
+			return Ok(());
+		}
+
+		// Else promote it if it's active, or mark it as accessed
+		// Note: The access happens before the promotion, so it's served by the previous memory.
+		page.hint_fault = false;
+		match page.active {
+			true =>
+				if let Err(err) = self.promote_page(cur_time, page_ptr, mem_idx) {
+					tracing::trace!(?page_ptr, ?err, "Unable to promote page");
+				},
+			false => self.mark_page_accessed(page_ptr, mem_idx),
+		}
+
+		let cur_temperature = self.pages.get(&page_ptr).expect("Page wasn't mapped").temperature();
+		self.tiered.access(
+			trace.record,
+			statistics::AccessMem::Resided(mem_idx),
+			self.config.hint_fault_latency,
+			prev_temperature,
+			cur_temperature,
+			false,
+		);
+
+		Ok(())
+	}
+
+	fn finish(&mut self) {
+		self.tiered.finish();
+	}
+
+	fn add_observer(&mut self, observer: impl Observer + 'static) {
+		self.tiered.add_observer(observer);
+	}
+
+	fn fmt_debug(&mut self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+		// Note: Start with a newline, since we're a multi-line output
+		f.pad("\n")?;
+
+		self.tiered.fmt_debug(f)?;
+		for (mem_idx, lists) in &self.lists {
+			writeln!(
+				f,
+				"Lists ({mem_idx:?}): {} active, {} inactive",
+				lists.active.len(),
+				lists.inactive.len()
+			)?;
+		}
+
+		Ok(())
+	}
+
+	fn debug_stats(&mut self) -> serde_json::Value {
+		self.tiered.debug_stats()
+	}
+}
+
+/// Configuration
+#[derive(Clone, Debug)]
+pub struct Config {
+	/// Fraction of free pages of a memory below which it's reclaimed
+	pub low_watermark: f64,
+
+	/// Fraction of free pages of a memory up to which it's reclaimed
+	pub high_watermark: f64,
+
+	/// Max pages scanned on each reclaim
+	pub reclaim_scan_pages: usize,
+
+	/// Period between each hint fault scan (in trace time)
+	pub scan_period: u64,
+
+	/// Pages scanned for hint faults on each memory, per scan
+	pub scan_pages: usize,
+
+	/// Latency of a hint fault
+	pub hint_fault_latency: FemtoDuration,
+}
+
+/// Page state
+#[derive(Clone, Copy, Default, Debug)]
+struct PageState {
+	/// Whether the page is on the active list
+	active: bool,
+
+	/// Whether the page was referenced since last checked
+	referenced: bool,
+
+	/// Whether the next access to the page will hint fault
+	hint_fault: bool,
+}
+
+impl PageState {
+	/// Returns the temperature of this page.
+	///
+	/// Inactive pages are 0, or 1 if referenced, while active pages are 2, or 3 if referenced.
+	fn temperature(&self) -> usize {
+		2 * usize::from(self.active) + usize::from(self.referenced)
+	}
+}
+
+/// LRU lists of a memory
+#[derive(Clone, Default, Debug)]
+struct Lists {
+	/// Active list
+	active: LruList<PagePtr>,
+
+	/// Inactive list
+	inactive: LruList<PagePtr>,
+}
@@ -10,10 +10,173 @@ pub struct Config {
 	/// Debug output period (in seconds)
 	pub debug_output_period_secs: f64,
 
+	/// CPU caches, from the first to the last level.
+	///
+	/// Only the misses and writebacks of the last level reach the classifier.
+	#[serde(default)]
+	pub caches: Vec<CacheConfig>,
+
+	/// Classifier.
+	///
+	/// All classifiers use the memories of the hemem configuration.
+	#[serde(default)]
+	pub classifier: ClassifierConfig,
+
 	/// Hemem configuration
 	pub hemem: HeMemConfig,
 }
 
+/// Classifier config
+#[derive(Default, Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ClassifierConfig {
+	/// HeMem, configured by `hemem`
+	#[default]
+	#[serde(rename = "hemem")]
+	HeMem,
+
+	/// TPP
+	Tpp(TppConfig),
+
+	/// AutoNUMA
+	#[serde(rename = "autonuma")]
+	AutoNuma(AutoNumaConfig),
+
+	/// Memtis
+	Memtis(MemtisConfig),
+
+	/// MGLRU
+	Mglru(MglruConfig),
+
+	/// First-touch placement
+	FirstTouch,
+
+	/// Interleaved placement
+	Interleave(InterleaveConfig),
+
+	/// Random placement
+	Random(RandomConfig),
+
+	/// ARC
+	Arc,
+
+	/// 2Q
+	TwoQ(TwoQConfig),
+}
+
+/// AutoNUMA config
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct AutoNumaConfig {
+	/// Period between each scan (in trace time)
+	pub scan_period: u64,
+
+	/// Pages unmapped on each memory, per scan
+	pub scan_pages: usize,
+
+	pub hint_fault_latency_ns: f64,
+
+	/// Max time between unmapping a page and it's hint fault for it to be promoted (in trace time).
+	///
+	/// If unset, all hint faults may promote.
+	#[serde(default)]
+	pub hot_threshold: Option<u64>,
+
+	/// Promotion rate limit (in MB/s).
+	///
+	/// If unset, promotions are unlimited.
+	#[serde(default)]
+	pub promote_rate_limit_mb_per_sec: Option<f64>,
+}
+
+/// Memtis config
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct MemtisConfig {
+	/// Period between each adaptation of the hot threshold (in trace time)
+	pub adaptation_period: u64,
+
+	/// Accesses of a page at which all pages are cooled
+	pub cooling_threshold: usize,
+}
+
+/// MGLRU config
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct MglruConfig {
+	/// Max generations per memory
+	pub generations: usize,
+
+	/// Period between each aging (in trace time)
+	pub aging_interval: u64,
+}
+
+/// Interleaved placement config
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct InterleaveConfig {
+	/// Pages to map to each memory at a time, in order
+	pub ratio: Vec<usize>,
+}
+
+/// Random placement config
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct RandomConfig {
+	/// Seed
+	pub seed: u64,
+}
+
+/// 2Q config
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct TwoQConfig {
+	/// Size of the recency list, as a fraction of the fastest memory's capacity
+	pub in_fraction: f64,
+
+	/// Size of the ghost list, as a fraction of the fastest memory's capacity
+	pub out_fraction: f64,
+}
+
+/// TPP config
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct TppConfig {
+	/// Fraction of free pages of a memory below which it's reclaimed
+	pub low_watermark: f64,
+
+	/// Fraction of free pages of a memory up to which it's reclaimed
+	pub high_watermark: f64,
+
+	/// Max pages scanned on each reclaim
+	pub reclaim_scan_pages: usize,
+
+	/// Period between each hint fault scan (in trace time)
+	pub scan_period: u64,
+
+	/// Pages scanned for hint faults on each memory, per scan
+	pub scan_pages: usize,
+
+	pub hint_fault_latency_ns: f64,
+}
+
+/// CPU cache config
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CacheConfig {
+	pub name:          String,
+	pub size_bytes:    u64,
+	pub associativity: usize,
+
+	/// Line size.
+	///
+	/// Must be a power of two no larger than a page.
+	/// If unset, defaults to 4KiB.
+	#[serde(default)]
+	pub line_bytes: Option<u64>,
+}
+
 /// HeMem config
 #[derive(Debug)]
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -22,6 +185,206 @@ pub struct HeMemConfig {
 	pub write_hot_threshold:      usize,
 	pub global_cooling_threshold: usize,
 	pub memories:                 Vec<HeMemMemory>,
+
+	/// Hotness estimator of each page.
+	///
+	/// If unset, defaults to counting accesses, halved on each global cooling.
+	#[serde(default)]
+	pub hotness: HeMemHotness,
+
+	/// Temperature of each page, as weights of it's estimated accesses.
+	///
+	/// If unset, defaults to `reads + 2 * writes`.
+	#[serde(default)]
+	pub temperature: HeMemTemperature,
+
+	/// Order in which cold pages are selected to be cooled or evicted.
+	///
+	/// If unset, defaults to the lowest addresses first.
+	#[serde(default)]
+	pub cold_page_selection: HeMemColdPageSelection,
+
+	/// Pages demoted at a time when cooling a memory.
+	///
+	/// If unset, defaults to [`HeMemConfig::DEFAULT_DEMOTION_BATCH`].
+	#[serde(default)]
+	pub demotion_batch: Option<usize>,
+
+	/// Epoch length for the per-epoch statistics.
+	///
+	/// If unset, no per-epoch statistics are kept.
+	#[serde(default)]
+	pub epoch_length: Option<EpochLength>,
+
+	/// Whether to keep a log of all accesses in the output.
+	///
+	/// Long runs may disable it and use the per-epoch statistics instead.
+	/// If unset, defaults to `true`.
+	#[serde(default)]
+	pub access_log: Option<bool>,
+
+	/// Behavior when all memories are full
+	#[serde(default)]
+	pub oom_behavior: OomBehavior,
+
+	/// Window over which the memory bandwidths are accounted (in nanoseconds).
+	///
+	/// If unset, defaults to [`HeMemConfig::DEFAULT_BANDWIDTH_WINDOW_NS`].
+	#[serde(default)]
+	pub bandwidth_window_ns: Option<u64>,
+
+	/// Swap memory, below the slowest memory.
+	///
+	/// Has unlimited capacity. Pages demoted out of the slowest memory under memory
+	/// pressure go here, and accessing them again swaps them back in with a major fault.
+	/// Required when `oom_behavior` is `swap`.
+	#[serde(default)]
+	pub swap: Option<HeMemSwap>,
+
+	/// TLB.
+	///
+	/// If unset, translations are free.
+	#[serde(default)]
+	pub tlb: Option<HeMemTlb>,
+
+	/// Transparent huge pages.
+	///
+	/// If unset, all memory is mapped with the page size of the tlb.
+	#[serde(default)]
+	pub thp: Option<HeMemThp>,
+}
+
+/// HeMem transparent huge pages
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct HeMemThp {
+	/// Accesses to a `2MiB` region between each check of it's skew
+	pub check_accesses: u64,
+
+	/// Minimum skew to split a huge page.
+	///
+	/// The skew is the ratio between the accesses to the hottest sub-page
+	/// and the mean accesses per sub-page, from 1 to 512.
+	pub split_skew: f64,
+
+	/// Maximum skew to collapse a region back into a huge page
+	pub collapse_skew: f64,
+
+	pub split_latency_ns:    f64,
+	pub collapse_latency_ns: f64,
+}
+
+/// HeMem TLB
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct HeMemTlb {
+	/// Page size the memory is mapped with (in bytes).
+	///
+	/// Ignored when using transparent huge pages.
+	pub page_size_bytes: u64,
+
+	/// Entries for each page size
+	pub entries: Vec<HeMemTlbEntries>,
+
+	/// Latency of a page walk, paid on each miss
+	pub page_walk_latency_ns: f64,
+}
+
+/// HeMem TLB entries for a page size
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct HeMemTlbEntries {
+	pub page_size_bytes: u64,
+	pub entries:         usize,
+	pub associativity:   usize,
+}
+
+/// Epoch length
+#[derive(Clone, Copy, Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EpochLength {
+	/// Trace time
+	Time(u64),
+
+	/// Number of trace records, including those filtered before reaching the classifier
+	Records(usize),
+}
+
+/// Temperature weights
+#[derive(Clone, Copy, Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct HeMemTemperature {
+	pub read_weight:  f64,
+	pub write_weight: f64,
+}
+
+impl Default for HeMemTemperature {
+	fn default() -> Self {
+		Self {
+			read_weight:  1.0,
+			write_weight: 2.0,
+		}
+	}
+}
+
+/// Hotness estimator
+#[derive(Clone, Copy, Default, Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HeMemHotness {
+	/// Counts accesses, halved on each global cooling
+	#[default]
+	HalvingClock,
+
+	/// Exponential moving average of the accesses, halving every `half_life` (in trace time).
+	///
+	/// Decays on it's own, so it's never globally cooled.
+	Ema { half_life: u64 },
+
+	/// Accesses within the last `window` (in trace time).
+	///
+	/// Decays on it's own, so it's never globally cooled.
+	SlidingWindow { window: u64 },
+}
+
+/// Cold page selection
+#[derive(Clone, Copy, Default, Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeMemColdPageSelection {
+	/// Lowest address first
+	#[default]
+	Address,
+
+	/// Lowest temperature first
+	Coldest,
+
+	/// Least recently accessed first
+	Lru,
+}
+
+impl HeMemConfig {
+	/// Default bandwidth window (in nanoseconds)
+	pub const DEFAULT_BANDWIDTH_WINDOW_NS: u64 = 1000;
+	/// Default demotion batch (in pages)
+	pub const DEFAULT_DEMOTION_BATCH: usize = 1;
+}
+
+/// Out-of-memory behavior
+#[derive(Clone, Copy, Default, Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OomBehavior {
+	/// Error out
+	#[default]
+	Error,
+
+	/// Drop the access
+	Drop,
+
+	/// Evict a page from the slowest memory to swap
+	Swap,
 }
 
 /// HeMem memory
@@ -33,4 +396,72 @@ pub struct HeMemMemory {
 	pub read_latency_ns:  f64,
 	pub write_latency_ns: f64,
 	pub fault_latency_ns: f64,
+
+	/// Energy per read of a cache line (in nanojoules)
+	#[serde(default)]
+	pub read_energy_nj: f64,
+
+	/// Energy per write of a cache line (in nanojoules)
+	#[serde(default)]
+	pub write_energy_nj: f64,
+
+	/// Static power per GB of capacity (in watts)
+	#[serde(default)]
+	pub static_power_w_per_gb: f64,
+
+	/// Cost per GB of capacity (in dollars)
+	#[serde(default)]
+	pub cost_per_gb: f64,
+
+	/// Read bandwidth (in GB/s).
+	///
+	/// If unset, reads are unlimited.
+	#[serde(default)]
+	pub read_bandwidth_gb_per_sec: Option<f64>,
+
+	/// Write bandwidth (in GB/s).
+	///
+	/// If unset, writes are unlimited.
+	#[serde(default)]
+	pub write_bandwidth_gb_per_sec: Option<f64>,
+
+	/// Write endurance (in writes per page before it wears out).
+	///
+	/// If set, the summary projects the lifetime of the memory.
+	#[serde(default)]
+	pub endurance_writes: Option<u64>,
+
+	/// Memories to promote pages to.
+	///
+	/// If unset, defaults to the next faster memory.
+	#[serde(default)]
+	pub promotion_targets: Option<Vec<HeMemTarget>>,
+
+	/// Memories to demote pages to.
+	///
+	/// If unset, defaults to the next slower memory.
+	#[serde(default)]
+	pub demotion_targets: Option<Vec<HeMemTarget>>,
+}
+
+/// HeMem migration target
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct HeMemTarget {
+	/// Memory name
+	pub name: String,
+
+	/// Distance to the memory.
+	///
+	/// Targets are tried from nearest to furthest.
+	pub distance: f64,
+}
+
+/// HeMem swap memory
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct HeMemSwap {
+	pub read_latency_ns:  f64,
+	pub write_latency_ns: f64,
+	pub fault_latency_ns: f64,
 }
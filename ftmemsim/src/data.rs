@@ -1,7 +1,7 @@
 //! Output data
 
 // Imports
-use std::{collections::BTreeMap, ops::Range};
+use std::{collections::BTreeMap, fmt, ops::Range};
 
 /// Output data
 #[derive(Debug)]
@@ -17,8 +17,30 @@ pub struct Data {
 #[derive(serde::Serialize, serde::Deserialize)]
 #[derive(bincode::Encode, bincode::Decode)]
 pub struct HeMemData {
-	pub page_accesses:   PageAccesses,
+	pub page_accesses:   Option<PageAccesses>,
 	pub page_migrations: PageMigrations,
+	pub epochs:          Vec<Epoch>,
+	pub page_writes:     Vec<PageWrites>,
+	pub thp_events:      Vec<ThpEvent>,
+	pub hot_thresholds:  Vec<HotThreshold>,
+
+	/// Definition of the page temperatures, if weighted by their accesses
+	pub temperature: Option<Temperature>,
+}
+
+/// Page temperature definition, as weights of the page's accesses
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(bincode::Encode, bincode::Decode)]
+pub struct Temperature {
+	pub read_weight:  f64,
+	pub write_weight: f64,
+}
+
+impl fmt::Display for Temperature {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} * reads + {} * writes", self.read_weight, self.write_weight)
+	}
 }
 
 /// Page accesses
@@ -75,3 +97,84 @@ pub struct PageMigration {
 	pub cur_mem_idx:  usize,
 	pub time:         u64,
 }
+
+/// Transparent huge page split or collapse
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(bincode::Encode, bincode::Decode)]
+pub struct ThpEvent {
+	pub time:       u64,
+	pub region_ptr: u64,
+	pub kind:       ThpEventKind,
+	pub cost_ns:    f64,
+}
+
+/// Transparent huge page event kind
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(bincode::Encode, bincode::Decode)]
+pub enum ThpEventKind {
+	Split,
+	Collapse,
+}
+
+/// Hot threshold change
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(bincode::Encode, bincode::Decode)]
+pub struct HotThreshold {
+	pub time:      u64,
+	pub threshold: usize,
+}
+
+/// Epoch
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(bincode::Encode, bincode::Decode)]
+pub struct Epoch {
+	pub start_time:       u64,
+	pub end_time:         u64,
+	pub records:          usize,
+	pub memories:         Vec<EpochMemory>,
+	pub fast_hit_rate:    f64,
+	pub promotions:       usize,
+	pub demotions:        usize,
+	pub coolings:         usize,
+	pub dropped_accesses: usize,
+	pub major_faults:     usize,
+	pub tlb_hits:         usize,
+	pub tlb_misses:       usize,
+	pub thp_splits:       usize,
+	pub thp_collapses:    usize,
+	pub thp_latency_ns:   f64,
+	pub hot_threshold:    Option<usize>,
+	pub latency_ns:       f64,
+}
+
+/// Epoch memory
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(bincode::Encode, bincode::Decode)]
+pub struct EpochMemory {
+	pub reads:             usize,
+	pub writes:            usize,
+	pub faults:            usize,
+	pub read_bytes:        u64,
+	pub write_bytes:       u64,
+	pub queueing_delay_ns: f64,
+	pub occupancy:         usize,
+	pub generation_sizes:  Vec<usize>,
+}
+
+/// Distribution of writes per page of a memory
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(bincode::Encode, bincode::Decode)]
+pub struct PageWrites {
+	pub pages: usize,
+	pub total: u64,
+	pub max:   u64,
+	pub p50:   u64,
+	pub p90:   u64,
+	pub p99:   u64,
+}
@@ -4,6 +4,7 @@
 #![feature(decl_macro, lint_reasons, get_many_mut, seek_stream_len)]
 
 // Modules
+pub mod cache;
 pub mod classifiers;
 pub mod config;
 pub mod data;
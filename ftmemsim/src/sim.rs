@@ -2,97 +2,251 @@
 
 // Imports
 use {
-	crate::{pin_trace, pin_trace::PinTraceReader},
+	crate::{
+		classifiers::observer::Observer,
+		pin_trace::{self, PinTraceReader},
+	},
 	anyhow::Context,
 	std::{
 		fmt,
 		io,
 		ops::Range,
+		sync::{self, mpsc},
+		thread,
 		time::{Duration, Instant},
 	},
 };
 
 /// Simulator
-#[derive(Debug)]
 pub struct Simulator {
-	/// Trace skip
-	///
-	/// Dictates how many records are skipped for each trace.
-	/// A value of 0 implies that the classifier receives all records as traces,
-	/// while a value of 1 implies it receives every other record as a trace.
-	trace_skip: usize,
-
 	/// Debug output period
 	///
 	/// Interval in which to output debug output for the classifier
 	debug_output_period: Duration,
+
+	/// Progress observers
+	///
+	/// Notified of the progress every debug output period, as well as once finished.
+	progress_observers: Vec<Box<dyn ProgressObserver>>,
 }
 
 impl Simulator {
+	/// Number of records read at once before handing them to the classifiers
+	pub const BATCH_LEN: usize = 4096;
+
 	/// Creates a new simulator
-	pub fn new(trace_skip: usize, debug_output_period: Duration) -> Self {
+	pub fn new(debug_output_period: Duration) -> Self {
 		Self {
-			trace_skip,
 			debug_output_period,
+			progress_observers: vec![],
 		}
 	}
 
-	/// Runs the simulator on all traces from `pin_trace_reader` with classifier `classifier`
-	pub fn run<C: Classifier>(
+	/// Adds a progress observer
+	pub fn add_progress_observer(&mut self, observer: impl ProgressObserver + 'static) {
+		self.progress_observers.push(Box::new(observer));
+	}
+
+	/// Runs the simulator on all traces from `pin_trace_reader` with all classifiers in `classifiers`.
+	///
+	/// The trace is only read once. Each classifier runs on it's own thread for the whole run,
+	/// and is sent each batch of records while the next batch is being read.
+	///
+	/// Each classifier skips records according to it's trace skip, in `trace_skips`. A trace skip
+	/// of 0 implies that the classifier receives all records as traces, while a value of 1 implies
+	/// it receives every other record as a trace.
+	///
+	/// # Errors
+	/// Returns an error if there isn't a trace skip for each classifier, if unable to read
+	/// the trace or if any classifier fails to handle a trace.
+	pub fn run<C: Classifier + Send>(
 		&mut self,
 		pin_trace_reader: &mut PinTraceReader<impl io::Read + io::Seek>,
-		classifier: &mut C,
+		classifiers: &mut [C],
+		trace_skips: &[usize],
 	) -> Result<RunOutput, anyhow::Error> {
+		anyhow::ensure!(
+			classifiers.len() == trace_skips.len(),
+			"Expected a trace skip for each classifier"
+		);
+
 		// Note: We start in the past so that we output right away at the start
-		let mut last_debug_time = Instant::now() - self.debug_output_period;
+		let start_time = Instant::now();
+		let mut last_debug_time = start_time - self.debug_output_period;
 
 		// Create the record iterator
 		let total_records = pin_trace_reader.records_remaining();
-		let record_it = std::iter::from_fn(|| pin_trace_reader.read_next().transpose());
+		let mut record_it = std::iter::from_fn(|| pin_trace_reader.read_next().transpose()).enumerate();
 
-		// Go through all records
+		// Reads the next batch of records, along with their index
 		let mut first_time = None;
 		let mut last_time = None;
-		let mut traces_since_log = 0;
-		for (record_idx, record_res) in record_it.enumerate().step_by(self.trace_skip + 1) {
-			let record = record_res.context("Unable to read next record")?;
-
-			// Update the first and last time.
-			// TODO: We're assuming all records are ordered by time, check when this *doesn't* happen
-			first_time.get_or_insert(record.time);
-			last_time = Some(record.time);
-
-			// Handle each trace
-			let trace = Trace { record };
-			classifier
-				.handle_trace(trace)
-				.context("Unable to handle trace with classifier")?;
-			traces_since_log += 1;
-
-			// Then show debug output, if it's been long enough
-			let cur_time = Instant::now();
-			let elapsed_debug_duration = cur_time.duration_since(last_debug_time);
-			if elapsed_debug_duration >= self.debug_output_period {
-				let estimated_time_left_secs = (total_records as f64 - record_idx as f64) /
-					(traces_since_log as f64 / elapsed_debug_duration.as_secs_f64());
-
-				let records_processed_percentage = 100.0 * (record_idx as f64 / total_records as f64);
-				tracing::info!(
-					"[{records_processed_percentage:.2}%] Estimated time left: {estimated_time_left_secs:.2}s",
-				);
-				tracing::info!(
-					"Debug: {}",
-					ftmemsim_util::DisplayWrapper::new(|f| classifier.fmt_debug(f))
-				);
-				last_debug_time = cur_time;
-				traces_since_log = 0;
+		let mut read_batch = || -> Result<Vec<(usize, pin_trace::Record)>, anyhow::Error> {
+			let mut batch = Vec::with_capacity(Self::BATCH_LEN);
+			for (record_idx, record_res) in record_it.by_ref().take(Self::BATCH_LEN) {
+				let record = record_res.context("Unable to read next record")?;
+
+				// Update the first and last time.
+				// TODO: We're assuming all records are ordered by time, check when this *doesn't* happen
+				first_time.get_or_insert(record.time);
+				last_time = Some(record.time);
+
+				batch.push((record_idx, record));
 			}
+
+			Ok(batch)
+		};
+
+		// Note: Only the workers touch the classifiers until the end, so we ask them for
+		//       any debug output, which they reply to once they're done with their previous batches.
+		let gather_stats = !self.progress_observers.is_empty();
+		let mut records_since_log = 0;
+		let traces = thread::scope(|s| {
+			// Start a worker for each classifier, handling every message it's sent
+			let (workers, handles): (Vec<_>, Vec<_>) = classifiers
+				.iter_mut()
+				.zip(trace_skips)
+				.map(|(classifier, &trace_skip)| {
+					let (msg_sender, msg_receiver) = mpsc::sync_channel::<WorkerMsg>(1);
+					let (debug_sender, debug_receiver) = mpsc::sync_channel::<WorkerDebug>(1);
+					let handle = s.spawn(move || {
+						let mut traces = 0;
+						for msg in msg_receiver {
+							match msg {
+								WorkerMsg::Batch(batch) =>
+									for &(record_idx, record) in batch.iter() {
+										if record_idx % (trace_skip + 1) == 0 {
+											classifier.register_trace(Trace { record });
+											classifier.handle_trace(Trace { record })?;
+											traces += 1;
+										}
+									},
+								WorkerMsg::Debug => {
+									let output =
+										ftmemsim_util::DisplayWrapper::new(|f| classifier.fmt_debug(f)).to_string();
+									let stats = match gather_stats {
+										true => classifier.debug_stats(),
+										false => serde_json::Value::Null,
+									};
+									// Note: If the reader hung up, it failed, and will report it's own error
+									if debug_sender.send(WorkerDebug { output, stats }).is_err() {
+										break;
+									}
+								},
+							}
+						}
+						classifier.finish();
+
+						Ok::<_, anyhow::Error>(traces)
+					});
+					((msg_sender, debug_receiver), handle)
+				})
+				.unzip();
+
+			// Then go through all batches
+			'read: loop {
+				let batch = read_batch()?;
+				let Some(&(record_idx, _)) = batch.last() else {
+					break;
+				};
+				records_since_log += batch.len();
+
+				// Note: If a worker hung up, it failed, so we stop and get it's error below
+				let batch = sync::Arc::<[_]>::from(batch);
+				for (msg_sender, _) in &workers {
+					if msg_sender.send(WorkerMsg::Batch(sync::Arc::clone(&batch))).is_err() {
+						break 'read;
+					}
+				}
+
+				// Then show debug output, if it's been long enough
+				let cur_time = Instant::now();
+				let elapsed_debug_duration = cur_time.duration_since(last_debug_time);
+				if elapsed_debug_duration >= self.debug_output_period {
+					let records_per_sec = records_since_log as f64 / elapsed_debug_duration.as_secs_f64();
+					let estimated_time_left_secs = (total_records as f64 - record_idx as f64) / records_per_sec;
+
+					let records_processed_percentage = 100.0 * (record_idx as f64 / total_records as f64);
+					tracing::info!(
+						"[{records_processed_percentage:.2}%] Estimated time left: {estimated_time_left_secs:.2}s",
+					);
+					for (msg_sender, _) in &workers {
+						if msg_sender.send(WorkerMsg::Debug).is_err() {
+							break 'read;
+						}
+					}
+					let mut classifier_stats = Vec::with_capacity(workers.len());
+					for (classifier_idx, (_, debug_receiver)) in workers.iter().enumerate() {
+						let Ok(worker_debug) = debug_receiver.recv() else {
+							break 'read;
+						};
+						tracing::info!("Debug [{classifier_idx}]: {}", worker_debug.output);
+						classifier_stats.push(worker_debug.stats);
+					}
+					self.notify_progress(Progress {
+						record_idx: record_idx as u64,
+						total_records,
+						records_per_sec,
+						estimated_time_left_secs,
+						classifiers: classifier_stats,
+					});
+
+					last_debug_time = cur_time;
+					records_since_log = 0;
+				}
+			}
+
+			// Finally let the workers finish and check if any of them failed
+			drop(workers);
+			handles
+				.into_iter()
+				.enumerate()
+				.map(|(classifier_idx, handle)| {
+					handle
+						.join()
+						.unwrap_or_else(|err| std::panic::resume_unwind(err))
+						.with_context(|| format!("Unable to handle trace with classifier {classifier_idx}"))
+				})
+				.collect::<Result<Vec<_>, _>>()
+		})?;
+
+		// Finally notify the final progress
+		let duration = start_time.elapsed();
+		if gather_stats {
+			self.notify_progress(Progress {
+				record_idx: total_records,
+				total_records,
+				records_per_sec: total_records as f64 / duration.as_secs_f64(),
+				estimated_time_left_secs: 0.0,
+				classifiers: classifiers
+					.iter_mut()
+					.map(|classifier| classifier.debug_stats())
+					.collect(),
+			});
 		}
 
 		Ok(RunOutput {
 			time_span: first_time.zip(last_time).map(|(first, last)| first..(last + 1)),
+			traces,
+			duration,
 		})
 	}
+
+	/// Notifies all progress observers of `progress`
+	fn notify_progress(&mut self, progress: Progress) {
+		for observer in &mut self.progress_observers {
+			observer.on_progress(&progress);
+		}
+	}
+}
+
+impl fmt::Debug for Simulator {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Simulator")
+			.field("debug_output_period", &self.debug_output_period)
+			.field("progress_observers", &self.progress_observers.len())
+			.finish()
+	}
 }
 
 /// Output for [`Simulator::run`]
@@ -100,15 +254,85 @@ impl Simulator {
 pub struct RunOutput {
 	/// Time span
 	pub time_span: Option<Range<u64>>,
+
+	/// Number of traces handled by each classifier
+	pub traces: Vec<usize>,
+
+	/// Wall-clock duration of the run
+	pub duration: Duration,
 }
 
 /// Classifier
 pub trait Classifier {
+	/// Registers a trace, before it's filtered by anything in front of the classifier.
+	///
+	/// Called for every trace, before [`Classifier::handle_trace`], which may then not be called for it.
+	fn register_trace(&mut self, trace: Trace) {
+		let _ = trace;
+	}
+
 	/// Handles a trace
 	fn handle_trace(&mut self, trace: Trace) -> Result<(), anyhow::Error>;
 
+	/// Finishes the run, once all traces were handled.
+	///
+	/// Flushes anything still pending, such as the last, partial, epoch.
+	fn finish(&mut self) {}
+
+	/// Adds an observer
+	fn add_observer(&mut self, observer: impl Observer + 'static);
+
 	/// Formats debug output to `f`.
 	fn fmt_debug(&mut self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error>;
+
+	/// Returns machine-readable debug stats
+	fn debug_stats(&mut self) -> serde_json::Value {
+		serde_json::Value::Null
+	}
+}
+
+/// Progress of [`Simulator::run`]
+#[derive(Clone, Debug)]
+#[derive(serde::Serialize)]
+pub struct Progress {
+	/// Index of the last record handled
+	pub record_idx: u64,
+
+	/// Total records
+	pub total_records: u64,
+
+	/// Records handled per second
+	pub records_per_sec: f64,
+
+	/// Estimated time left (in seconds)
+	pub estimated_time_left_secs: f64,
+
+	/// Debug stats of each classifier
+	pub classifiers: Vec<serde_json::Value>,
+}
+
+/// Progress observer
+pub trait ProgressObserver {
+	/// Called with the current progress of the simulator
+	fn on_progress(&mut self, progress: &Progress);
+}
+
+/// Message sent to a worker
+enum WorkerMsg {
+	/// Batch of records to handle, along with their index
+	Batch(sync::Arc<[(usize, pin_trace::Record)]>),
+
+	/// Request for the debug output of the classifier
+	Debug,
+}
+
+/// Debug output of a worker's classifier
+struct WorkerDebug {
+	/// Formatted debug output
+	output: String,
+
+	/// Debug stats, if gathered
+	stats: serde_json::Value,
 }
 
 /// Trace